@@ -0,0 +1,211 @@
+use core::convert::Infallible;
+use core::time::Duration;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+use axum::extract::{FromRequestParts, Query};
+use axum::http::request::Parts;
+use reqwest::StatusCode;
+use serde::{Deserialize, Deserializer};
+use strum::IntoStaticStr;
+
+use crate::error::{APIError, APIResult};
+
+const API_KEY_HEADER: &str = "X-API-Key";
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Endpoints that open a stream to the upstream broadcast.
+#[derive(Deserialize, IntoStaticStr, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Endpoint {
+    Events,
+    Demo,
+    Schema,
+}
+
+/// A client API key and the limits that apply to it.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClientApiKey {
+    pub key: String,
+    /// Maximum number of streams the key may have open at the same time.
+    #[serde(default)]
+    pub max_concurrent_streams: Option<usize>,
+    /// Maximum number of streams the key may open within a minute.
+    #[serde(default)]
+    pub max_streams_per_minute: Option<usize>,
+    /// Endpoints the key may use, all if unset.
+    #[serde(default)]
+    pub allowed_endpoints: Option<Vec<Endpoint>>,
+}
+
+/// Parses the client API keys from a JSON encoded list, as they are passed via the environment.
+pub(crate) fn client_api_keys_deserialize<'de, D>(
+    deserializer: D,
+) -> Result<Vec<ClientApiKey>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(keys) if !keys.trim().is_empty() => {
+            serde_json::from_str(&keys).map_err(serde::de::Error::custom)
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiKeyQuery {
+    #[serde(default)]
+    api_key: Option<String>,
+}
+
+/// API key sent by the client in the `X-API-Key` header or the `api_key` query parameter.
+pub(crate) struct ClientKey(Option<String>);
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientKey {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(ToOwned::to_owned);
+        Ok(Self(header.or_else(|| {
+            Query::<ApiKeyQuery>::try_from_uri(&parts.uri)
+                .ok()
+                .and_then(|q| q.0.api_key)
+        })))
+    }
+}
+
+#[derive(Default)]
+struct KeyUsage {
+    active_streams: usize,
+    recent_streams: VecDeque<Instant>,
+}
+
+/// Authenticates clients and enforces the limits of their API keys.
+///
+/// If no keys are configured, the server is open and no limits apply.
+#[derive(Clone, Default)]
+pub(crate) struct ClientKeys {
+    keys: Arc<HashMap<String, ClientApiKey>>,
+    usage: Arc<Mutex<HashMap<String, KeyUsage>>>,
+}
+
+impl ClientKeys {
+    pub(crate) fn new(keys: &[ClientApiKey]) -> Self {
+        Self {
+            keys: Arc::new(keys.iter().map(|k| (k.key.clone(), k.clone())).collect()),
+            usage: Arc::default(),
+        }
+    }
+
+    /// Checks that the client sent a valid API key, returns the key and its limits.
+    ///
+    /// Returns `None` if the server is open because no keys are configured.
+    pub(crate) fn authenticate(&self, client_key: &ClientKey) -> APIResult<Option<&ClientApiKey>> {
+        if self.keys.is_empty() {
+            return Ok(None);
+        }
+        let Some(key) = client_key.0.as_deref() else {
+            return Err(APIError::StatusMsg {
+                status: StatusCode::UNAUTHORIZED,
+                message: "Missing API key.".to_owned(),
+            });
+        };
+        match self.keys.get(key) {
+            Some(limits) => Ok(Some(limits)),
+            None => Err(APIError::StatusMsg {
+                status: StatusCode::UNAUTHORIZED,
+                message: "Invalid API key.".to_owned(),
+            }),
+        }
+    }
+
+    /// Checks that the client may open a new stream on `endpoint` and counts it against its limits.
+    ///
+    /// The stream is counted as open until the returned permit is dropped.
+    pub(crate) fn acquire(
+        &self,
+        client_key: &ClientKey,
+        endpoint: Endpoint,
+    ) -> APIResult<Option<StreamPermit>> {
+        let Some(limits) = self.authenticate(client_key)? else {
+            return Ok(None);
+        };
+        let key = limits.key.as_str();
+        if limits
+            .allowed_endpoints
+            .as_ref()
+            .is_some_and(|allowed| !allowed.contains(&endpoint))
+        {
+            return Err(APIError::StatusMsg {
+                status: StatusCode::FORBIDDEN,
+                message: format!(
+                    "API key is not allowed to use the {} endpoint.",
+                    <&str>::from(endpoint)
+                ),
+            });
+        }
+
+        let mut usage = self.usage.lock().unwrap_or_else(PoisonError::into_inner);
+        let usage = usage.entry(key.to_owned()).or_default();
+        let now = Instant::now();
+        while usage
+            .recent_streams
+            .front()
+            .is_some_and(|started| now.duration_since(*started) >= RATE_LIMIT_WINDOW)
+        {
+            usage.recent_streams.pop_front();
+        }
+        if let Some(max) = limits
+            .max_concurrent_streams
+            .filter(|&max| usage.active_streams >= max)
+        {
+            return Err(APIError::StatusMsg {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                message: format!("Too many concurrent streams, at most {max} are allowed."),
+            });
+        }
+        if let Some(max) = limits
+            .max_streams_per_minute
+            .filter(|&max| usage.recent_streams.len() >= max)
+        {
+            return Err(APIError::StatusMsg {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                message: format!("Too many new streams, at most {max} per minute are allowed."),
+            });
+        }
+        usage.active_streams += 1;
+        usage.recent_streams.push_back(now);
+
+        Ok(Some(StreamPermit {
+            clients: self.clone(),
+            key: key.to_owned(),
+        }))
+    }
+}
+
+/// Counts a stream against the concurrency limit of its API key for as long as it is alive.
+pub(crate) struct StreamPermit {
+    clients: ClientKeys,
+    key: String,
+}
+
+impl Drop for StreamPermit {
+    fn drop(&mut self) {
+        if let Some(usage) = self
+            .clients
+            .usage
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_mut(&self.key)
+        {
+            usage.active_streams = usage.active_streams.saturating_sub(1);
+        }
+    }
+}
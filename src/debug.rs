@@ -0,0 +1,87 @@
+use core::time::Duration;
+use std::sync::PoisonError;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use haste::broadcast::BroadcastHttp;
+use haste::parser::Parser;
+use serde::Deserialize;
+use tracing::{debug, info};
+
+use crate::auth::{ClientKey, Endpoint};
+use crate::demo_parser::error::DemoParseError;
+use crate::demo_parser::schema::{SchemaReport, SchemaVisitor};
+use crate::error::APIResult;
+use crate::state::AppState;
+use crate::utils;
+
+const DEFAULT_SCHEMA_DURATION_SECS: u64 = 10;
+const MAX_SCHEMA_DURATION_SECS: u64 = 60;
+
+#[derive(Deserialize)]
+pub(super) struct SchemaQuery {
+    /// How long to parse the stream for at most, in seconds.
+    #[serde(default)]
+    duration_secs: Option<u64>,
+}
+
+async fn inspect_schema(
+    broadcast_url: impl Into<String>,
+    duration: Duration,
+) -> Result<SchemaReport, DemoParseError> {
+    let client = reqwest::Client::new();
+    let demo_stream = BroadcastHttp::start_streaming(client, broadcast_url).await?;
+    let visitor = SchemaVisitor::default();
+    let report = visitor.report();
+    let mut parser = Parser::from_stream_with_visitor(demo_stream, visitor)?;
+
+    let parse = async {
+        while !report
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_complete()
+        {
+            match parser.demo_stream_mut().next_packet().await {
+                Some(Ok(_)) => parser.run_to_end().await?,
+                Some(Err(e)) => return Err(DemoParseError::from(e)),
+                None => break,
+            }
+        }
+        Ok::<(), DemoParseError>(())
+    };
+    match tokio::time::timeout(duration, parse).await {
+        Ok(result) => result?,
+        Err(_) => debug!("Schema inspection timed out, reporting entity types seen so far"),
+    }
+
+    let report = core::mem::take(&mut *report.lock().unwrap_or_else(PoisonError::into_inner));
+    Ok(report.finish())
+}
+
+pub(super) async fn schema(
+    Path(match_id): Path<u64>,
+    Query(query): Query<SchemaQuery>,
+    State(state): State<AppState>,
+    client_key: ClientKey,
+) -> APIResult<impl IntoResponse> {
+    state.ensure_accepting_streams()?;
+    let _permit = state.clients.acquire(&client_key, Endpoint::Schema)?;
+    let broadcast_url = utils::spectate_live_demo(
+        &state.http_client,
+        &state.config.deadlock_api_base_url,
+        match_id,
+        state.config.deadlock_api_key.as_deref(),
+    )
+    .await?;
+
+    let duration = Duration::from_secs(
+        query
+            .duration_secs
+            .unwrap_or(DEFAULT_SCHEMA_DURATION_SECS)
+            .min(MAX_SCHEMA_DURATION_SECS),
+    );
+    info!("Inspecting entity schema of match {match_id}");
+    let report = inspect_schema(broadcast_url, duration).await?;
+    Ok(Json(report))
+}
@@ -1,4 +1,4 @@
-use core::time::Duration;
+use std::time::Instant;
 
 use async_stream::try_stream;
 use axum::body::Body;
@@ -7,24 +7,45 @@ use axum::response::IntoResponse;
 use bytes::Bytes;
 use futures::Stream;
 use haste::broadcast::{BroadcastHttp, BroadcastHttpClientError};
-use tracing::info;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
 
-use crate::error::{APIError, APIResult};
+use crate::auth::{ClientKey, Endpoint};
+use crate::error::APIResult;
 use crate::state::AppState;
-use crate::utils::{live_demo_exists, spectate_match};
+use crate::streams::StreamHandle;
+use crate::telemetry;
+use crate::utils::spectate_live_demo;
 
 fn demo_stream(
+    stream_handle: StreamHandle,
     broadcast_url: impl Into<String>,
+    shutdown: CancellationToken,
 ) -> impl Stream<Item = Result<Bytes, BroadcastHttpClientError<reqwest::Error>>> {
     let client = reqwest::Client::new();
     try_stream! {
+        let _stream_handle = stream_handle;
         let mut demofile = BroadcastHttp::start_streaming(
             client,
             broadcast_url,
         ).await?;
-        while let Some(chunk) = demofile.next_packet().await {
+        loop {
+            let fetch_start = Instant::now();
+            let packet = tokio::select! {
+                packet = demofile.next_packet() => packet,
+                () = shutdown.cancelled() => {
+                    debug!("Server shutting down, ending demo stream");
+                    None
+                }
+            };
+            let Some(chunk) = packet else {
+                break;
+            };
+            telemetry::record_fragment_fetch(fetch_start.elapsed().as_secs_f64(), chunk.is_ok());
             info!("Received chunk");
-            yield chunk?;
+            let chunk = chunk?;
+            telemetry::record_bytes_sent("demo", chunk.len());
+            yield chunk;
         }
     }
 }
@@ -32,27 +53,21 @@ fn demo_stream(
 pub(super) async fn demo(
     Path(match_id): Path<u64>,
     State(state): State<AppState>,
+    client_key: ClientKey,
 ) -> APIResult<impl IntoResponse> {
-    info!("Spectating match {match_id}");
-    let response = tryhard::retry_fn(|| {
-        spectate_match(
-            &state.http_client,
-            match_id,
-            state.config.deadlock_api_key.as_ref().map(AsRef::as_ref),
-        )
-    })
-    .retries(3)
-    .fixed_backoff(Duration::from_millis(200))
+    state.ensure_accepting_streams()?;
+    let permit = state.clients.acquire(&client_key, Endpoint::Demo)?;
+    let broadcast_url = spectate_live_demo(
+        &state.http_client,
+        &state.config.deadlock_api_base_url,
+        match_id,
+        state.config.deadlock_api_key.as_deref(),
+    )
     .await?;
 
-    // Wait for the demo to be available
-    tryhard::retry_fn(|| async {
-        live_demo_exists(&state.http_client, &response.broadcast_url).await
-    })
-    .retries(60)
-    .fixed_backoff(Duration::from_millis(500))
-    .await
-    .map_err(|e| APIError::internal(format!("Failed to spectate match: {e}")))?;
-
-    Ok(Body::from_stream(demo_stream(response.broadcast_url)))
+    Ok(Body::from_stream(demo_stream(
+        state.streams.register(match_id, Endpoint::Demo, permit),
+        broadcast_url,
+        state.shutdown.clone(),
+    )))
 }
@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+
+/// Restricts which chat messages are sent and masks unwanted words in them.
+#[derive(Debug, Default)]
+pub(crate) struct ChatFilter {
+    /// Only send all-chat messages, hiding team communication.
+    pub(crate) all_chat_only: bool,
+    /// Only send messages of players on this team.
+    pub(crate) team: Option<u8>,
+    /// Lowercase words replaced by asterisks.
+    masked_words: HashSet<String>,
+}
+
+impl ChatFilter {
+    pub(crate) fn new(all_chat_only: bool, team: Option<u8>, masked_words: &[String]) -> Self {
+        Self {
+            all_chat_only,
+            team,
+            masked_words: masked_words.iter().map(|w| w.to_lowercase()).collect(),
+        }
+    }
+
+    /// Whether a message sent to `all_chat` by a player of `team` passes the filter.
+    pub(crate) fn allows(&self, all_chat: Option<bool>, team: Option<u8>) -> bool {
+        if self.all_chat_only && all_chat != Some(true) {
+            return false;
+        }
+        self.team.is_none() || self.team == team
+    }
+
+    /// Replaces every masked word in `text` by asterisks, ignoring case.
+    pub(crate) fn mask(&self, text: &str) -> String {
+        if self.masked_words.is_empty() {
+            return text.to_string();
+        }
+        let mut masked = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(char::is_alphanumeric) {
+            masked.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric())
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            if self.masked_words.contains(&word.to_lowercase()) {
+                masked.extend(core::iter::repeat_n('*', word.chars().count()));
+            } else {
+                masked.push_str(word);
+            }
+            rest = &rest[end..];
+        }
+        masked.push_str(rest);
+        masked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(masked_words: &[&str]) -> ChatFilter {
+        let masked_words: Vec<String> = masked_words.iter().map(ToString::to_string).collect();
+        ChatFilter::new(false, None, &masked_words)
+    }
+
+    #[test]
+    fn mask_ignores_case() {
+        assert_eq!(filter(&["Bad"]).mask("bad BAD bAd"), "*** *** ***");
+    }
+
+    #[test]
+    fn mask_keeps_punctuation() {
+        assert_eq!(
+            filter(&["bad"]).mask("(bad), bad... bad!bad?"),
+            "(***), ***... ***!***?"
+        );
+    }
+
+    #[test]
+    fn mask_only_matches_whole_words() {
+        assert_eq!(filter(&["bad"]).mask("badge abad bad1"), "badge abad bad1");
+    }
+
+    #[test]
+    fn mask_handles_unicode() {
+        assert_eq!(filter(&["ärger"]).mask("So ein ÄRGER!"), "So ein *****!");
+        assert_eq!(filter(&["дурак"]).mask("ты Дурак"), "ты *****");
+        assert_eq!(filter(&["bad"]).mask("bad 🙂 bad"), "*** 🙂 ***");
+    }
+
+    #[test]
+    fn mask_without_words_keeps_text() {
+        assert_eq!(filter(&[]).mask("bad"), "bad");
+    }
+
+    #[test]
+    fn allows_filters_all_chat_and_team() {
+        let all_chat_only = ChatFilter::new(true, None, &[]);
+        assert!(all_chat_only.allows(Some(true), Some(2)));
+        assert!(!all_chat_only.allows(Some(false), Some(2)));
+        assert!(!all_chat_only.allows(None, Some(2)));
+
+        let team = ChatFilter::new(false, Some(2), &[]);
+        assert!(team.allows(Some(false), Some(2)));
+        assert!(!team.allows(Some(true), Some(3)));
+        assert!(!team.allows(Some(true), None));
+    }
+}
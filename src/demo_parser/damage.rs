@@ -0,0 +1,18 @@
+use serde::Serialize;
+
+use crate::demo_parser::entity_events::EntityType;
+
+/// Damage flag set on critical hits, which are headshots in Deadlock.
+pub(super) const CRIT_DAMAGE_FLAG: u64 = 1 << 15;
+
+/// The attacker or victim of a hit, resolved to a player if it is a player pawn.
+#[derive(Serialize, Debug, Clone, Default)]
+pub(super) struct DamageParticipant {
+    pub(super) entity_index: Option<i32>,
+    /// Type of the entity, if it is one of the tracked entity types.
+    pub(super) entity_type: Option<EntityType>,
+    pub(super) team: Option<u8>,
+    pub(super) controller: Option<i32>,
+    pub(super) steam_id: Option<u32>,
+    pub(super) hero_id: Option<u32>,
+}
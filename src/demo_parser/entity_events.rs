@@ -7,6 +7,7 @@ use strum::{Display, EnumString, FromRepr, VariantArray};
 
 #[allow(clippy::wildcard_imports)]
 use crate::demo_parser::hashes::*;
+use crate::demo_parser::modifiers::ActiveModifier;
 use crate::demo_parser::types::Delta;
 use crate::demo_parser::utils;
 use crate::utils::steamid64_to_steamid3;
@@ -49,11 +50,29 @@ pub(crate) enum EntityType {
     DestroyableBuilding = fxhash::hash_bytes(b"CCitadel_Destroyable_Building"),
     SinnersSacrifice = fxhash::hash_bytes(b"CNPC_Neutral_SinnersSacrifice"),
     AbilityMeleeParry = fxhash::hash_bytes(b"CCitadel_Ability_MeleeParry"),
+    /// Any other hero ability or item, matched by class name prefix in [`Self::from_opt`].
+    Ability = fxhash::hash_bytes(b"CCitadelBaseAbility"),
 }
 
+/// Hero abilities are `CCitadel_Ability_<Hero>_*` classes, items are `CCitadel_Item` or a
+/// `CCitadel_Item_*` class.
+const ABILITY_CLASS_PREFIXES: &[&str] = &["CCitadel_Ability_", "CCitadel_Item"];
+
 impl EntityType {
     pub(super) fn from_opt(entity: &Entity) -> Option<Self> {
-        Self::from_repr(entity.serializer().serializer_name.hash)
+        let serializer_name = &entity.serializer().serializer_name;
+        Self::from_repr(serializer_name.hash).or_else(|| {
+            ABILITY_CLASS_PREFIXES
+                .iter()
+                .any(|prefix| serializer_name.str.starts_with(prefix))
+                .then_some(Self::Ability)
+        })
+    }
+
+    /// Whether updates of this type are sent without subscribing to it, abilities are opt-in
+    /// as every player has dozens of them.
+    pub(crate) fn is_default(self) -> bool {
+        self != Self::Ability
     }
 }
 
@@ -66,9 +85,18 @@ pub(super) trait EntityUpdateEvent: Serialize {
 #[derive(Serialize, Debug, Clone, Default)]
 pub(super) struct GameRulesProxyEvent {
     pub(super) game_start_time: Option<f32>,
-    game_paused: Option<bool>,
-    pause_start_tick: Option<i32>,
+    pub(super) game_paused: Option<bool>,
+    pub(super) pause_start_tick: Option<i32>,
+    pub(super) pause_team: Option<i32>,
     pub(super) total_paused_ticks: Option<i32>,
+    pub(super) winning_team: Option<u8>,
+}
+
+impl GameRulesProxyEvent {
+    /// The team that won the match, once the game rules declare one of the two playing teams.
+    pub(super) fn winner(&self) -> Option<u8> {
+        self.winning_team.filter(|team| matches!(team, 2 | 3))
+    }
 }
 
 impl EntityUpdateEvent for GameRulesProxyEvent {
@@ -77,7 +105,9 @@ impl EntityUpdateEvent for GameRulesProxyEvent {
             game_start_time: entity.get_value(&START_TIME_HASH),
             game_paused: entity.get_value(&PAUSED_HASH),
             pause_start_tick: entity.get_value(&PAUSE_START_TICK_HASH),
+            pause_team: entity.get_value(&PAUSE_TEAM_HASH),
             total_paused_ticks: entity.get_value(&PAUSED_TICKS_HASH),
+            winning_team: entity.get_value(&WINNING_TEAM_HASH),
         }
         .into()
     }
@@ -85,13 +115,13 @@ impl EntityUpdateEvent for GameRulesProxyEvent {
 
 #[derive(Serialize, Debug, Clone, Default)]
 pub(super) struct PlayerControllerEvent {
-    pawn: Option<i32>,
-    steam_id: Option<u32>,
-    steam_name: Option<String>,
-    team: Option<u8>,
-    hero_id: Option<u32>,
+    pub(super) pawn: Option<i32>,
+    pub(super) steam_id: Option<u32>,
+    pub(super) steam_name: Option<String>,
+    pub(super) team: Option<u8>,
+    pub(super) hero_id: Option<u32>,
     hero_badge_xp: Option<u32>,
-    player_slot: Option<u8>,
+    pub(super) player_slot: Option<u8>,
     rank: Option<i32>, // Currently always 0 or None, as Valve hides rank data
     assigned_lane: Option<i8>,
     original_assigned_lane: Option<i8>,
@@ -108,9 +138,23 @@ pub(super) struct PlayerControllerEvent {
     hero_damage: Option<i32>,
     objective_damage: Option<i32>,
     ultimate_cooldown_end: Option<f32>,
+    /// Server time at which the player respawns while dead.
+    pub(super) respawn_time: Option<f32>,
     upgrades: Vec<u64>,
 }
 
+#[cfg(test)]
+impl PlayerControllerEvent {
+    pub(super) fn for_player(pawn: i32, steam_id: u32, hero_id: u32) -> Self {
+        Self {
+            pawn: Some(pawn),
+            steam_id: Some(steam_id),
+            hero_id: Some(hero_id),
+            ..Default::default()
+        }
+    }
+}
+
 impl EntityUpdateEvent for PlayerControllerEvent {
     fn from_entity_update(_ctx: &Context, _delta_header: Delta, entity: &Entity) -> Option<Self> {
         Self {
@@ -139,6 +183,7 @@ impl EntityUpdateEvent for PlayerControllerEvent {
             hero_damage: entity.get_value(&HERO_DAMAGE_HASH),
             objective_damage: entity.get_value(&OBJECTIVE_DAMAGE_HASH),
             ultimate_cooldown_end: entity.get_value(&ULTIMATE_COOLDOWN_END_HASH),
+            respawn_time: entity.get_value(&RESPAWN_TIME_HASH),
             upgrades: (0..entity.get_value(&UPGRADES_HASH).unwrap_or_default())
                 .map(|i| add_u64_to_hash(UPGRADES_HASH, add_u64_to_hash(0, i)))
                 .filter_map(|h| entity.get_value(&h))
@@ -161,7 +206,12 @@ pub(super) struct PlayerPawnEvent {
     level: Option<i32>,
     max_health: Option<i32>,
     health: Option<i32>,
+    /// 0 while alive, 1 while dying and 2 while dead.
+    life_state: Option<u8>,
     position: Option<[f32; 3]>,
+    /// Active modifiers, only tracked if the client subscribed to them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) modifiers: Option<Vec<ActiveModifier>>,
 }
 
 impl EntityUpdateEvent for PlayerPawnEvent {
@@ -175,7 +225,9 @@ impl EntityUpdateEvent for PlayerPawnEvent {
             level: entity.get_value(&LEVEL_HASH),
             max_health: entity.get_value(&MAX_HEALTH_HASH),
             health: entity.get_value(&HEALTH_HASH),
+            life_state: entity.get_value(&LIFE_STATE_HASH),
             position: utils::get_entity_position(entity),
+            modifiers: None,
             quickbuy_auto_purchase: entity.get_value(&QUICKBUY_AUTO_PURCHASE_HASH),
             quickbuy_auto_queue_build: entity.get_value(&QUICKBUY_AUTO_QUUE_BUILD_HASH),
             quickbuy_queue: (0..entity.get_value(&QUICKBUY_HASH).unwrap_or_default())
@@ -291,6 +343,42 @@ impl EntityUpdateEvent for AbilityMeleeParry {
     }
 }
 
+#[derive(Serialize, Debug, Clone, Default)]
+pub(super) struct AbilityEvent {
+    ability_class: String,
+    ability_id: Option<u32>,
+    owner_entity: Option<i32>,
+    controller: Option<i32>,
+    steam_id: Option<u32>,
+    upgrade_bits: Option<u32>,
+    cooldown_start: Option<f32>,
+    cooldown_end: Option<f32>,
+    remaining_charges: Option<i32>,
+    channeling: Option<bool>,
+}
+
+impl EntityUpdateEvent for AbilityEvent {
+    fn from_entity_update(ctx: &Context, _delta_header: Delta, entity: &Entity) -> Option<Self> {
+        let owner_entity = entity.get_value(&OWNER_ENTITY_HASH).map(ehandle_to_index);
+        let (controller, steam_id) = owner_entity
+            .map(|pawn| utils::get_pawn_player(ctx, pawn))
+            .unwrap_or_default();
+        Self {
+            ability_class: entity.serializer().serializer_name.str.to_string(),
+            ability_id: entity.get_value(&SUBCLASS_ID_HASH),
+            owner_entity,
+            controller,
+            steam_id,
+            upgrade_bits: entity.get_value(&UPGRADE_BITS_HASH),
+            cooldown_start: entity.get_value(&COOLDOWN_START_HASH),
+            cooldown_end: entity.get_value(&COOLDOWN_END_HASH),
+            remaining_charges: entity.get_value(&REMAINING_CHARGES_HASH),
+            channeling: entity.get_value(&CHANNELING_HASH),
+        }
+        .into()
+    }
+}
+
 #[derive(Serialize, Debug, Clone, Default)]
 pub(super) struct PositionActiveEntity {
     active: bool,
@@ -344,6 +432,7 @@ pub(super) enum EntityUpdateEvents {
     DestroyableBuilding(Box<DestroyableBuilding>),
     AbilityMeleeParry(Box<AbilityMeleeParry>),
     SinnersSacrifice(Box<SinnersSacrifice>),
+    Ability(Box<AbilityEvent>),
 }
 
 impl EntityUpdateEvents {
@@ -428,6 +517,27 @@ impl EntityUpdateEvents {
                     .map(Box::new)
                     .map(Self::AbilityMeleeParry)
             }
+            EntityType::Ability => AbilityEvent::from_entity_update(ctx, delta, entity)
+                .map(Box::new)
+                .map(Self::Ability),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winner_is_one_of_the_playing_teams() {
+        let rules = |winning_team| GameRulesProxyEvent {
+            winning_team,
+            ..Default::default()
+        };
+        assert_eq!(rules(None).winner(), None);
+        assert_eq!(rules(Some(0)).winner(), None);
+        assert_eq!(rules(Some(1)).winner(), None);
+        assert_eq!(rules(Some(2)).winner(), Some(2));
+        assert_eq!(rules(Some(3)).winner(), Some(3));
+    }
+}
@@ -3,9 +3,11 @@ use haste::broadcast::BroadcastHttpClientError;
 use haste::demofile::DemoHeaderError;
 use haste::demostream::{DecodeCmdError, ReadCmdError, ReadCmdHeaderError};
 use haste::flattenedserializers::FlattenedSerializersError;
+use strum::IntoStaticStr;
 use tokio::sync::mpsc::error::SendError;
 
-#[derive(thiserror::Error, Debug)]
+#[derive(thiserror::Error, Debug, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
 pub(crate) enum DemoParseError {
     #[error(transparent)]
     Send(#[from] SendError<Event>),
@@ -29,4 +31,13 @@ pub(crate) enum DemoParseError {
     FlattenedSerializers(#[from] FlattenedSerializersError),
     #[error(transparent)]
     SSEEvent(#[from] axum::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl DemoParseError {
+    /// Machine-readable name of the error variant, e.g. `decode_cmd`.
+    pub(crate) fn kind(&self) -> &'static str {
+        self.into()
+    }
 }
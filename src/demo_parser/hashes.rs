@@ -1,64 +1,91 @@
 use haste::entities::fkey_from_path;
 use haste::fxhash;
 
-pub(super) const CONTROLLER_HASH: u64 = fxhash::hash_bytes(b"m_hController");
-pub(super) const OWNER_ENTITY_HASH: u64 = fxhash::hash_bytes(b"m_hOwnerEntity");
-pub(super) const PAWN_HASH: u64 = fxhash::hash_bytes(b"m_hPawn");
-pub(super) const STEAM_ID_HASH: u64 = fxhash::hash_bytes(b"m_steamID");
-pub(super) const STEAM_NAME_HASH: u64 = fxhash::hash_bytes(b"m_iszPlayerName");
-pub(super) const HERO_BUILD_ID_HASH: u64 = fxhash::hash_bytes(b"m_unHeroBuildID");
-pub(super) const HERO_BUILD_SERIALIZED_HASH: u64 = fxhash::hash_bytes(b"m_sHeroBuildSerialized");
-pub(super) const HERO_BADGE_XP_HASH: u64 =
-    fkey_from_path(&["m_PlayerDataGlobal", "m_unHeroBadgeXP"]);
-pub(super) const LEVEL_HASH: u64 = fxhash::hash_bytes(b"m_nLevel");
-pub(super) const TEAM_HASH: u64 = fxhash::hash_bytes(b"m_iTeamNum");
-pub(super) const SCORE_HASH: u64 = fxhash::hash_bytes(b"m_iScore");
-pub(super) const TEAMNAME_HASH: u64 = fxhash::hash_bytes(b"m_szTeamname");
-pub(super) const FLEX_UNLOCKED_HASH: u64 = fxhash::hash_bytes(b"m_nFlexSlotsUnlocked");
-pub(super) const HEALTH_HASH: u64 = fxhash::hash_bytes(b"m_iHealth");
-pub(super) const MAX_HEALTH_HASH: u64 = fxhash::hash_bytes(b"m_iMaxHealth");
-pub(super) const HERO_ID_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_nHeroID"]);
-pub(super) const PLAYER_SLOT_HASH: u64 = fxhash::hash_bytes(b"m_unLobbyPlayerSlot");
-pub(super) const RANK_HASH: u64 = fxhash::hash_bytes(b"m_nCurrentRank");
-pub(super) const ASSIGNED_LANE_HASH: u64 = fxhash::hash_bytes(b"m_nAssignedLane");
-pub(super) const ORIGINAL_ASSIGNED_LANE_HASH: u64 =
-    fxhash::hash_bytes(b"m_nOriginalLaneAssignment");
-pub(super) const NET_WORTH_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_iGoldNetWorth"]);
-pub(super) const HEALTH_REGEN_HASH: u64 = fxhash::hash_bytes(b"m_flHealthRegen");
-pub(super) const ULTIMATE_TRAINED_HASH: u64 =
-    fkey_from_path(&["m_PlayerDataGlobal", "m_bUltimateTrained"]);
-pub(super) const KILLS_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_iPlayerKills"]);
-pub(super) const ASSISTS_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_iPlayerAssists"]);
-pub(super) const DEATHS_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_iDeaths"]);
-pub(super) const DENIES_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_iDenies"]);
-pub(super) const LAST_HITS_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_iLastHits"]);
-pub(super) const HERO_HEALING_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_iHeroHealing"]);
-pub(super) const SELF_HEALING_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_iSelfHealing"]);
-pub(super) const HERO_DAMAGE_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_iHeroDamage"]);
-pub(super) const OBJECTIVE_DAMAGE_HASH: u64 =
-    fkey_from_path(&["m_PlayerDataGlobal", "m_iObjectiveDamage"]);
-pub(super) const ULTIMATE_COOLDOWN_END_HASH: u64 =
-    fkey_from_path(&["m_PlayerDataGlobal", "m_flUltimateCooldownEnd"]);
-pub(super) const UPGRADES_HASH: u64 = fkey_from_path(&["m_PlayerDataGlobal", "m_vecUpgrades"]);
-pub(super) const QUICKBUY_AUTO_PURCHASE_HASH: u64 = fxhash::hash_bytes(b"m_bQuickbuyAutoPurchase");
-pub(super) const QUICKBUY_AUTO_QUUE_BUILD_HASH: u64 =
-    fxhash::hash_bytes(b"m_bQuickbuyAutoQueueBuild");
-pub(super) const QUICKBUY_HASH: u64 = fxhash::hash_bytes(b"m_vecQuickbuyQueue");
-pub(super) const CREATE_TIME_HASH: u64 = fxhash::hash_bytes(b"m_flCreateTime");
-pub(super) const ATTACK_PARRIED_HASH: u64 = fxhash::hash_bytes(b"m_bAttackParried");
-pub(super) const PARRY_START_TIME_HASH: u64 = fxhash::hash_bytes(b"m_flParryStartTime");
-pub(super) const PARRY_SUCCESS_TIME_HASH: u64 = fxhash::hash_bytes(b"m_flParrySuccessTime");
-pub(super) const LANE_HASH: u64 = fxhash::hash_bytes(b"m_iLane");
-pub(super) const SHIELD_ACTIVE_HASH: u64 = fxhash::hash_bytes(b"m_bShieldActive");
-pub(super) const ACTIVE_HASH: u64 = fxhash::hash_bytes(b"m_bActive");
-pub(super) const CX: u64 = fkey_from_path(&["CBodyComponent", "m_cellX"]);
-pub(super) const CY: u64 = fkey_from_path(&["CBodyComponent", "m_cellY"]);
-pub(super) const CZ: u64 = fkey_from_path(&["CBodyComponent", "m_cellZ"]);
-pub(super) const VX: u64 = fkey_from_path(&["CBodyComponent", "m_vecX"]);
-pub(super) const VY: u64 = fkey_from_path(&["CBodyComponent", "m_vecY"]);
-pub(super) const VZ: u64 = fkey_from_path(&["CBodyComponent", "m_vecZ"]);
-pub(super) const START_TIME_HASH: u64 = fkey_from_path(&["m_pGameRules", "m_flGameStartTime"]);
-pub(super) const PAUSED_HASH: u64 = fkey_from_path(&["m_pGameRules", "m_bGamePaused"]);
-pub(super) const PAUSE_START_TICK_HASH: u64 =
-    fkey_from_path(&["m_pGameRules", "m_nPauseStartTick"]);
-pub(super) const PAUSED_TICKS_HASH: u64 = fkey_from_path(&["m_pGameRules", "m_nTotalPausedTicks"]);
+/// Key of the field at `path`, top level fields are keyed by the hash of their name.
+const fn field_key(path: &[&str]) -> u64 {
+    if path.len() == 1 {
+        fxhash::hash_bytes(path[0].as_bytes())
+    } else {
+        fkey_from_path(path)
+    }
+}
+
+/// Declares the key of every field read by the extractors, along with its path in [`paths`], so
+/// the schema report checks exactly the fields that are read.
+macro_rules! fields {
+    ($($constant:ident => [$($segment:literal),+]),* $(,)?) => {
+        $(pub(super) const $constant: u64 = field_key(&[$($segment),+]);)*
+
+        /// Field paths by the name of their key constant.
+        pub(super) mod paths {
+            $(pub(in crate::demo_parser) const $constant: &[&str] = &[$($segment),+];)*
+        }
+    };
+}
+
+fields! {
+    CONTROLLER_HASH => ["m_hController"],
+    OWNER_ENTITY_HASH => ["m_hOwnerEntity"],
+    PAWN_HASH => ["m_hPawn"],
+    STEAM_ID_HASH => ["m_steamID"],
+    STEAM_NAME_HASH => ["m_iszPlayerName"],
+    HERO_BUILD_ID_HASH => ["m_unHeroBuildID"],
+    HERO_BUILD_SERIALIZED_HASH => ["m_sHeroBuildSerialized"],
+    HERO_BADGE_XP_HASH => ["m_PlayerDataGlobal", "m_unHeroBadgeXP"],
+    LIFE_STATE_HASH => ["m_lifeState"],
+    RESPAWN_TIME_HASH => ["m_PlayerDataGlobal", "m_flRespawnTime"],
+    LEVEL_HASH => ["m_nLevel"],
+    TEAM_HASH => ["m_iTeamNum"],
+    SCORE_HASH => ["m_iScore"],
+    TEAMNAME_HASH => ["m_szTeamname"],
+    FLEX_UNLOCKED_HASH => ["m_nFlexSlotsUnlocked"],
+    HEALTH_HASH => ["m_iHealth"],
+    MAX_HEALTH_HASH => ["m_iMaxHealth"],
+    HERO_ID_HASH => ["m_PlayerDataGlobal", "m_nHeroID"],
+    PLAYER_SLOT_HASH => ["m_unLobbyPlayerSlot"],
+    RANK_HASH => ["m_nCurrentRank"],
+    ASSIGNED_LANE_HASH => ["m_nAssignedLane"],
+    ORIGINAL_ASSIGNED_LANE_HASH => ["m_nOriginalLaneAssignment"],
+    NET_WORTH_HASH => ["m_PlayerDataGlobal", "m_iGoldNetWorth"],
+    HEALTH_REGEN_HASH => ["m_flHealthRegen"],
+    ULTIMATE_TRAINED_HASH => ["m_PlayerDataGlobal", "m_bUltimateTrained"],
+    KILLS_HASH => ["m_PlayerDataGlobal", "m_iPlayerKills"],
+    ASSISTS_HASH => ["m_PlayerDataGlobal", "m_iPlayerAssists"],
+    DEATHS_HASH => ["m_PlayerDataGlobal", "m_iDeaths"],
+    DENIES_HASH => ["m_PlayerDataGlobal", "m_iDenies"],
+    LAST_HITS_HASH => ["m_PlayerDataGlobal", "m_iLastHits"],
+    HERO_HEALING_HASH => ["m_PlayerDataGlobal", "m_iHeroHealing"],
+    SELF_HEALING_HASH => ["m_PlayerDataGlobal", "m_iSelfHealing"],
+    HERO_DAMAGE_HASH => ["m_PlayerDataGlobal", "m_iHeroDamage"],
+    OBJECTIVE_DAMAGE_HASH => ["m_PlayerDataGlobal", "m_iObjectiveDamage"],
+    ULTIMATE_COOLDOWN_END_HASH => ["m_PlayerDataGlobal", "m_flUltimateCooldownEnd"],
+    UPGRADES_HASH => ["m_PlayerDataGlobal", "m_vecUpgrades"],
+    QUICKBUY_AUTO_PURCHASE_HASH => ["m_bQuickbuyAutoPurchase"],
+    QUICKBUY_AUTO_QUUE_BUILD_HASH => ["m_bQuickbuyAutoQueueBuild"],
+    QUICKBUY_HASH => ["m_vecQuickbuyQueue"],
+    CREATE_TIME_HASH => ["m_flCreateTime"],
+    UPGRADE_BITS_HASH => ["m_nUpgradeBits"],
+    SUBCLASS_ID_HASH => ["m_nSubclassID"],
+    COOLDOWN_START_HASH => ["m_flCooldownStart"],
+    COOLDOWN_END_HASH => ["m_flCooldownEnd"],
+    REMAINING_CHARGES_HASH => ["m_iRemainingCharges"],
+    CHANNELING_HASH => ["m_bChanneling"],
+    ATTACK_PARRIED_HASH => ["m_bAttackParried"],
+    PARRY_START_TIME_HASH => ["m_flParryStartTime"],
+    PARRY_SUCCESS_TIME_HASH => ["m_flParrySuccessTime"],
+    LANE_HASH => ["m_iLane"],
+    SHIELD_ACTIVE_HASH => ["m_bShieldActive"],
+    ACTIVE_HASH => ["m_bActive"],
+    CX => ["CBodyComponent", "m_cellX"],
+    CY => ["CBodyComponent", "m_cellY"],
+    CZ => ["CBodyComponent", "m_cellZ"],
+    VX => ["CBodyComponent", "m_vecX"],
+    VY => ["CBodyComponent", "m_vecY"],
+    VZ => ["CBodyComponent", "m_vecZ"],
+    START_TIME_HASH => ["m_pGameRules", "m_flGameStartTime"],
+    PAUSED_HASH => ["m_pGameRules", "m_bGamePaused"],
+    PAUSE_START_TICK_HASH => ["m_pGameRules", "m_nPauseStartTick"],
+    PAUSE_TEAM_HASH => ["m_pGameRules", "m_iPauseTeam"],
+    PAUSED_TICKS_HASH => ["m_pGameRules", "m_nTotalPausedTicks"],
+    WINNING_TEAM_HASH => ["m_pGameRules", "m_iWinningTeam"],
+}
@@ -0,0 +1,126 @@
+/// Timings of a killed mid boss, in game time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct MidBossKill {
+    pub(super) spawn_time: Option<f32>,
+    pub(super) alive_duration: Option<f32>,
+    /// When the next mid boss spawns.
+    pub(super) respawn_time: f32,
+}
+
+/// Follows the mid boss from its spawn to its death.
+///
+/// The mid boss entity only tells that it spawned, the killer is taken from the `BossKilled`
+/// message.
+#[derive(Debug, Default)]
+pub(super) struct MidBossTracker {
+    /// Seconds between the death of the mid boss and its respawn.
+    respawn_delay: f32,
+    /// Entity index of the living mid boss.
+    entity: Option<i32>,
+    /// Game time at which the living mid boss spawned.
+    spawn_time: Option<f32>,
+}
+
+impl MidBossTracker {
+    pub(super) fn new(respawn_delay: f32) -> Self {
+        Self {
+            respawn_delay,
+            ..Default::default()
+        }
+    }
+
+    /// Records a newly created mid boss entity, returns whether it was not known yet.
+    pub(super) fn spawn(&mut self, entity_index: i32, spawn_time: Option<f32>) -> bool {
+        if self.entity == Some(entity_index) {
+            return false;
+        }
+        self.entity = Some(entity_index);
+        self.spawn_time = spawn_time;
+        true
+    }
+
+    /// Clears the living mid boss if `entity_index` is it, returns its timings.
+    pub(super) fn kill(&mut self, entity_index: i32, game_time: f32) -> Option<MidBossKill> {
+        if self.entity != Some(entity_index) {
+            return None;
+        }
+        self.entity = None;
+        let spawn_time = self.spawn_time.take();
+        Some(MidBossKill {
+            spawn_time,
+            alive_duration: spawn_time.map(|t| (game_time - t).max(0.0)),
+            respawn_time: game_time + self.respawn_delay,
+        })
+    }
+
+    /// Forgets a deleted mid boss entity that was not reported as killed.
+    pub(super) fn remove(&mut self, entity_index: i32) {
+        if self.entity == Some(entity_index) {
+            self.entity = None;
+            self.spawn_time = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawns_once() {
+        let mut tracker = MidBossTracker::new(420.0);
+        assert!(tracker.spawn(5, Some(600.0)));
+        assert!(!tracker.spawn(5, Some(600.0)));
+    }
+
+    #[test]
+    fn kill_reports_timings() {
+        let mut tracker = MidBossTracker::new(420.0);
+        tracker.spawn(5, Some(600.0));
+        assert_eq!(
+            tracker.kill(5, 900.0),
+            Some(MidBossKill {
+                spawn_time: Some(600.0),
+                alive_duration: Some(300.0),
+                respawn_time: 1320.0,
+            })
+        );
+        assert_eq!(tracker.kill(5, 901.0), None);
+    }
+
+    #[test]
+    fn kill_of_other_entities_is_ignored() {
+        let mut tracker = MidBossTracker::new(420.0);
+        assert_eq!(tracker.kill(5, 900.0), None);
+        tracker.spawn(5, None);
+        assert_eq!(tracker.kill(6, 900.0), None);
+        assert_eq!(
+            tracker.kill(5, 900.0),
+            Some(MidBossKill {
+                spawn_time: None,
+                alive_duration: None,
+                respawn_time: 1320.0,
+            })
+        );
+    }
+
+    #[test]
+    fn respawn_is_a_new_spawn() {
+        let mut tracker = MidBossTracker::new(420.0);
+        tracker.spawn(5, Some(600.0));
+        tracker.kill(5, 900.0);
+        assert!(tracker.spawn(5, Some(1320.0)));
+        assert_eq!(
+            tracker.kill(5, 1400.0).and_then(|k| k.alive_duration),
+            Some(80.0)
+        );
+    }
+
+    #[test]
+    fn removed_mid_boss_is_not_killed() {
+        let mut tracker = MidBossTracker::new(420.0);
+        tracker.spawn(5, Some(600.0));
+        tracker.remove(5);
+        assert_eq!(tracker.kill(5, 900.0), None);
+    }
+}
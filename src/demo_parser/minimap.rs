@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Coordinate system of positions sent to the client.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Coords {
+    /// Raw world coordinates.
+    #[default]
+    World,
+    /// World coordinates plus normalized minimap coordinates and zone labels.
+    Minimap,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Lane {
+    Left,
+    Middle,
+    Right,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Area {
+    Base,
+    Lane,
+    Jungle,
+}
+
+/// Rough location of a position on the map, lanes are seen from the base at the bottom.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+struct Zone {
+    /// Team whose half of the map the position is on.
+    side: u8,
+    area: Area,
+    lane: Option<Lane>,
+}
+
+/// Layout of the map in normalized minimap coordinates, configured next to the map bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct MapZones {
+    /// Horizontal centers of the left, middle and right lane.
+    pub(crate) lane_centers: [f32; 3],
+    /// Half the width of a lane.
+    pub(crate) lane_half_width: f32,
+    /// Distance from the center line of the map beyond which a position is in a team's base.
+    pub(crate) base_distance: f32,
+    /// Team whose base is at the bottom of the minimap, the other team's base is at the top.
+    pub(crate) bottom_team: u8,
+}
+
+/// Converts world positions into minimap coordinates.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Minimap {
+    min: [f32; 2],
+    max: [f32; 2],
+    zones: MapZones,
+}
+
+impl Minimap {
+    /// The bounds have to be checked by `Config::validate`, `min` has to be below `max`.
+    pub(crate) fn new(min: [f32; 2], max: [f32; 2], zones: MapZones) -> Self {
+        Self { min, max, zones }
+    }
+
+    /// Normalizes a world position to `[0, 1]`, with the origin at the top left of the minimap.
+    fn normalize(&self, position: [f32; 3]) -> [f32; 2] {
+        let x = (position[0] - self.min[0]) / (self.max[0] - self.min[0]);
+        let y = (self.max[1] - position[1]) / (self.max[1] - self.min[1]);
+        [x.clamp(0.0, 1.0), y.clamp(0.0, 1.0)]
+    }
+
+    /// Labels a normalized position.
+    fn zone(&self, normalized: [f32; 2]) -> Zone {
+        let [x, y] = normalized;
+        let MapZones {
+            lane_centers,
+            lane_half_width,
+            base_distance,
+            bottom_team,
+        } = self.zones;
+        let top_team = if bottom_team == 2 { 3 } else { 2 };
+        let side = if y >= 0.5 { bottom_team } else { top_team };
+        if (y - 0.5).abs() > base_distance {
+            return Zone {
+                side,
+                area: Area::Base,
+                lane: None,
+            };
+        }
+        let lane = [Lane::Left, Lane::Middle, Lane::Right]
+            .into_iter()
+            .zip(lane_centers)
+            .find(|(_, center)| (x - center).abs() <= lane_half_width)
+            .map(|(lane, _)| lane);
+        Zone {
+            side,
+            area: if lane.is_some() {
+                Area::Lane
+            } else {
+                Area::Jungle
+            },
+            lane,
+        }
+    }
+
+    /// Adds `minimap_position` and `zone` next to every `position` in a serialized event.
+    pub(super) fn annotate(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                for nested in object.values_mut() {
+                    self.annotate(nested);
+                }
+                self.annotate_position(object);
+            }
+            Value::Array(array) => {
+                for nested in array {
+                    self.annotate(nested);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn annotate_position(&self, object: &mut Map<String, Value>) {
+        let Some(position) = object
+            .get("position")
+            .and_then(|p| serde_json::from_value::<[f32; 3]>(p.clone()).ok())
+        else {
+            return;
+        };
+        let normalized = self.normalize(position);
+        object.insert("minimap_position".to_owned(), normalized.to_vec().into());
+        if let Ok(zone) = serde_json::to_value(self.zone(normalized)) {
+            object.insert("zone".to_owned(), zone);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZONES: MapZones = MapZones {
+        lane_centers: [0.2, 0.5, 0.8],
+        lane_half_width: 0.07,
+        base_distance: 0.38,
+        bottom_team: 2,
+    };
+
+    fn minimap(zones: MapZones) -> Minimap {
+        Minimap::new([-1000.0, -2000.0], [1000.0, 2000.0], zones)
+    }
+
+    #[test]
+    fn normalize_puts_the_origin_at_the_top_left() {
+        let minimap = minimap(ZONES);
+        assert_eq!(minimap.normalize([-1000.0, 2000.0, 0.0]), [0.0, 0.0]);
+        assert_eq!(minimap.normalize([1000.0, -2000.0, 0.0]), [1.0, 1.0]);
+        assert_eq!(minimap.normalize([0.0, 0.0, 500.0]), [0.5, 0.5]);
+        assert_eq!(minimap.normalize([500.0, 1000.0, 0.0]), [0.75, 0.25]);
+    }
+
+    #[test]
+    fn normalize_clamps_positions_outside_the_bounds() {
+        assert_eq!(minimap(ZONES).normalize([-5000.0, 5000.0, 0.0]), [0.0, 0.0]);
+        assert_eq!(minimap(ZONES).normalize([5000.0, -5000.0, 0.0]), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn zone_labels_bases() {
+        let minimap = minimap(ZONES);
+        assert_eq!(
+            minimap.zone([0.5, 0.95]),
+            Zone {
+                side: 2,
+                area: Area::Base,
+                lane: None
+            }
+        );
+        assert_eq!(
+            minimap.zone([0.5, 0.05]),
+            Zone {
+                side: 3,
+                area: Area::Base,
+                lane: None
+            }
+        );
+    }
+
+    #[test]
+    fn zone_labels_lanes_and_jungle() {
+        let minimap = minimap(ZONES);
+        assert_eq!(
+            minimap.zone([0.22, 0.7]),
+            Zone {
+                side: 2,
+                area: Area::Lane,
+                lane: Some(Lane::Left)
+            }
+        );
+        assert_eq!(minimap.zone([0.5, 0.3]).lane, Some(Lane::Middle));
+        assert_eq!(minimap.zone([0.75, 0.6]).lane, Some(Lane::Right));
+        assert_eq!(
+            minimap.zone([0.35, 0.4]),
+            Zone {
+                side: 3,
+                area: Area::Jungle,
+                lane: None
+            }
+        );
+    }
+
+    #[test]
+    fn zone_follows_the_configured_layout() {
+        let minimap = minimap(MapZones {
+            lane_centers: [0.1, 0.4, 0.9],
+            lane_half_width: 0.05,
+            base_distance: 0.3,
+            bottom_team: 3,
+        });
+        assert_eq!(
+            minimap.zone([0.5, 0.85]),
+            Zone {
+                side: 3,
+                area: Area::Base,
+                lane: None
+            }
+        );
+        assert_eq!(minimap.zone([0.41, 0.3]).side, 2);
+        assert_eq!(minimap.zone([0.41, 0.3]).lane, Some(Lane::Middle));
+        assert_eq!(minimap.zone([0.5, 0.3]).area, Area::Jungle);
+    }
+}
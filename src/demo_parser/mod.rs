@@ -1,6 +1,15 @@
+pub(crate) mod chat;
+mod damage;
 pub(crate) mod entity_events;
 pub(crate) mod error;
 mod hashes;
+mod mid_boss;
+pub(crate) mod minimap;
+mod modifiers;
+pub(crate) mod region;
+pub(crate) mod schema;
 pub(crate) mod types;
+mod urn;
+pub(crate) mod user_messages;
 mod utils;
 pub(crate) mod visitor;
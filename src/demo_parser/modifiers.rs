@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, HashMap};
+
+use haste::entities::ehandle_to_index;
+use haste::fxhash;
+use haste::parser::Context;
+use prost::Message;
+use serde::Serialize;
+use valveprotos::common::{CsvcMsgCreateStringTable, CsvcMsgUpdateStringTable, SvcMessages};
+use valveprotos::deadlock::{CModifierTableEntry, ModifierEntryType};
+
+const MODIFIER_TABLE: &str = "ActiveModifiers";
+
+/// A buff or debuff applied to an entity.
+#[derive(Serialize, Debug, Clone)]
+pub(super) struct ActiveModifier {
+    pub(super) modifier_id: Option<i32>,
+    serial_number: i32,
+    /// Entity the modifier is applied to.
+    pub(super) target: Option<i32>,
+    /// Entity that applied the modifier.
+    caster: Option<i32>,
+    /// Ability that applied the modifier.
+    ability: Option<i32>,
+    stack_count: Option<i32>,
+    duration: Option<f32>,
+    applied_time: Option<f32>,
+}
+
+fn handle_to_index(handle: impl TryInto<u32>) -> Option<i32> {
+    handle.try_into().ok().map(ehandle_to_index)
+}
+
+impl From<&CModifierTableEntry> for ActiveModifier {
+    fn from(entry: &CModifierTableEntry) -> Self {
+        Self {
+            modifier_id: entry.modifier_subclass,
+            serial_number: entry.serial_number,
+            target: handle_to_index(entry.parent),
+            caster: entry.caster.and_then(handle_to_index),
+            ability: entry.ability.and_then(handle_to_index),
+            stack_count: entry.stack_count,
+            duration: entry.duration,
+            applied_time: entry.last_applied_time,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum ModifierChange {
+    Added(ActiveModifier),
+    Removed(ActiveModifier),
+}
+
+/// Follows the modifier string table and keeps the set of active modifiers.
+#[derive(Default)]
+pub(super) struct ModifierTracker {
+    /// Number of string tables created so far, tables are identified by their creation order.
+    created_tables: i32,
+    /// Id of the modifier table once it was created.
+    table_id: Option<i32>,
+    /// Whether the modifier table changed since the last call to [`Self::update`].
+    table_changed: bool,
+    /// Hash of the last seen user data of every table item, to only decode items that changed.
+    items: HashMap<i32, u64>,
+    /// Active modifiers by parent handle (`uint32`) and serial number (`int32`), the key of a
+    /// `CModifierTableEntry`.
+    active: BTreeMap<(u32, i32), ActiveModifier>,
+}
+
+impl ModifierTracker {
+    /// Notes changes to the modifier table, so [`Self::update`] only reads it after it changed.
+    pub(super) fn on_packet(&mut self, packet_type: u32, data: &[u8]) {
+        if packet_type == SvcMessages::SvcCreateStringTable as u32
+            && let Ok(msg) = CsvcMsgCreateStringTable::decode(data)
+        {
+            if msg.name() == MODIFIER_TABLE {
+                self.table_id = Some(self.created_tables);
+                self.table_changed = true;
+            }
+            self.created_tables += 1;
+        } else if packet_type == SvcMessages::SvcUpdateStringTable as u32
+            && let Ok(msg) = CsvcMsgUpdateStringTable::decode(data)
+            && msg.table_id.is_some()
+            && msg.table_id == self.table_id
+        {
+            self.table_changed = true;
+        }
+    }
+
+    /// Applies the changes to the modifier table since the last call.
+    pub(super) fn update(&mut self, ctx: &Context) -> Vec<ModifierChange> {
+        if !core::mem::take(&mut self.table_changed) {
+            return vec![];
+        }
+        let Some(table) = ctx
+            .string_tables()
+            .and_then(|tables| tables.find_table(MODIFIER_TABLE))
+        else {
+            return vec![];
+        };
+        table
+            .items()
+            .filter_map(|(&index, item)| self.apply(index, item.get_user_data()?.as_ref()))
+            .collect()
+    }
+
+    /// Applies the user data of a table item, if it changed since it was last seen.
+    fn apply(&mut self, index: i32, data: &[u8]) -> Option<ModifierChange> {
+        let hash = fxhash::hash_bytes(data);
+        if self.items.insert(index, hash) == Some(hash) {
+            return None;
+        }
+        let entry = CModifierTableEntry::decode(data).ok()?;
+        let key = (entry.parent, entry.serial_number);
+        if entry.entry_type() == ModifierEntryType::ModifierEntryTypeRemoved {
+            self.active.remove(&key).map(ModifierChange::Removed)
+        } else {
+            let modifier = ActiveModifier::from(&entry);
+            self.active
+                .insert(key, modifier.clone())
+                .is_none()
+                .then_some(ModifierChange::Added(modifier))
+        }
+    }
+
+    /// Modifiers currently applied to the entity at `target`.
+    pub(super) fn of_target(&self, target: i32) -> Vec<ActiveModifier> {
+        self.active
+            .values()
+            .filter(|m| m.target == Some(target))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(parent: u32, serial_number: i32, removed: bool) -> Vec<u8> {
+        let mut entry = CModifierTableEntry {
+            parent,
+            serial_number,
+            ..Default::default()
+        };
+        if removed {
+            entry.set_entry_type(ModifierEntryType::ModifierEntryTypeRemoved);
+        }
+        entry.encode_to_vec()
+    }
+
+    #[test]
+    fn new_entries_are_added() {
+        let mut tracker = ModifierTracker::default();
+        assert!(matches!(
+            tracker.apply(0, &entry(0x4001, 1, false)),
+            Some(ModifierChange::Added(m)) if m.serial_number == 1 && m.target == Some(1)
+        ));
+        assert!(matches!(
+            tracker.apply(1, &entry(0x4001, 2, false)),
+            Some(ModifierChange::Added(m)) if m.serial_number == 2
+        ));
+        assert_eq!(tracker.of_target(1).len(), 2);
+    }
+
+    #[test]
+    fn unchanged_entries_are_skipped() {
+        let mut tracker = ModifierTracker::default();
+        tracker.apply(0, &entry(0x4001, 1, false));
+        assert!(tracker.apply(0, &entry(0x4001, 1, false)).is_none());
+    }
+
+    #[test]
+    fn updated_entries_are_not_added_twice() {
+        let mut tracker = ModifierTracker::default();
+        tracker.apply(0, &entry(0x4001, 1, false));
+        let mut updated =
+            CModifierTableEntry::decode(entry(0x4001, 1, false).as_slice()).expect("Invalid entry");
+        updated.stack_count = Some(2);
+        assert!(tracker.apply(0, &updated.encode_to_vec()).is_none());
+        assert_eq!(tracker.of_target(1).len(), 1);
+    }
+
+    #[test]
+    fn removed_entries_are_removed_once() {
+        let mut tracker = ModifierTracker::default();
+        tracker.apply(0, &entry(0x4001, 1, false));
+        assert!(matches!(
+            tracker.apply(0, &entry(0x4001, 1, true)),
+            Some(ModifierChange::Removed(m)) if m.serial_number == 1
+        ));
+        assert!(tracker.of_target(1).is_empty());
+        assert!(tracker.apply(1, &entry(0x4001, 1, true)).is_none());
+    }
+
+    #[test]
+    fn removing_an_unknown_entry_is_ignored() {
+        let mut tracker = ModifierTracker::default();
+        assert!(tracker.apply(0, &entry(0x4001, 7, true)).is_none());
+    }
+}
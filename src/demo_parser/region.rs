@@ -0,0 +1,215 @@
+use core::str::FromStr;
+use std::collections::HashSet;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Area of the map in world coordinates, parsed from `x1,y1,x2,y2` for a box or `x,y,radius`
+/// for a circle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Region {
+    Rect { min: [f32; 2], max: [f32; 2] },
+    Circle { center: [f32; 2], radius: f32 },
+}
+
+impl Region {
+    fn contains(&self, position: [f32; 3]) -> bool {
+        let [x, y, _] = position;
+        match *self {
+            Self::Rect { min, max } => {
+                (min[0]..=max[0]).contains(&x) && (min[1]..=max[1]).contains(&y)
+            }
+            Self::Circle { center, radius } => {
+                (x - center[0]).powi(2) + (y - center[1]).powi(2) <= radius.powi(2)
+            }
+        }
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid region {s}: {e}"))?;
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(format!("Invalid region {s}: values must be finite"));
+        }
+        match values[..] {
+            [x1, y1, x2, y2] => Ok(Self::Rect {
+                min: [x1.min(x2), y1.min(y2)],
+                max: [x1.max(x2), y1.max(y2)],
+            }),
+            [x, y, radius] if radius >= 0.0 => Ok(Self::Circle {
+                center: [x, y],
+                radius,
+            }),
+            _ => Err(format!(
+                "Invalid region {s}: expected x1,y1,x2,y2 or x,y,radius"
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Region {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for Region {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let region = match self {
+            Self::Rect { min, max } => format!("{},{},{},{}", min[0], min[1], max[0], max[1]),
+            Self::Circle { center, radius } => format!("{},{},{radius}", center[0], center[1]),
+        };
+        serializer.serialize_str(&region)
+    }
+}
+
+/// Whether an entity crossed the boundary of the region with an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RegionChange {
+    Entered,
+    Left,
+}
+
+/// Keeps track of the entities inside a region.
+#[derive(Debug)]
+pub(super) struct RegionTracker {
+    region: Region,
+    inside: HashSet<i32>,
+}
+
+impl RegionTracker {
+    pub(super) fn new(region: Region) -> Self {
+        Self {
+            region,
+            inside: HashSet::new(),
+        }
+    }
+
+    /// Records the position of an entity, returns whether it crossed the boundary.
+    pub(super) fn update(&mut self, entity_index: i32, position: [f32; 3]) -> Option<RegionChange> {
+        if self.region.contains(position) {
+            self.inside
+                .insert(entity_index)
+                .then_some(RegionChange::Entered)
+        } else {
+            self.inside
+                .remove(&entity_index)
+                .then_some(RegionChange::Left)
+        }
+    }
+
+    pub(super) fn is_inside(&self, entity_index: i32) -> bool {
+        self.inside.contains(&entity_index)
+    }
+
+    /// Forgets a deleted entity, returns whether it was inside the region.
+    pub(super) fn remove(&mut self, entity_index: i32) -> bool {
+        self.inside.remove(&entity_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_boxes_in_any_corner_order() {
+        assert_eq!(
+            "10,-20, -30 ,40".parse(),
+            Ok(Region::Rect {
+                min: [-30.0, -20.0],
+                max: [10.0, 40.0]
+            })
+        );
+    }
+
+    #[test]
+    fn parses_circles() {
+        assert_eq!(
+            "1.5,2,100".parse(),
+            Ok(Region::Circle {
+                center: [1.5, 2.0],
+                radius: 100.0
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_regions() {
+        for region in [
+            "",
+            "1,2",
+            "1,2,3,4,5",
+            "a,b,c",
+            "1,2,-3",
+            "nan,0,1,1",
+            "0,0,inf",
+            "0,0,1,-inf",
+        ] {
+            assert!(region.parse::<Region>().is_err(), "{region}");
+        }
+    }
+
+    #[test]
+    fn contains_includes_the_boundary() {
+        let rect = Region::Rect {
+            min: [0.0, 0.0],
+            max: [10.0, 10.0],
+        };
+        assert!(rect.contains([0.0, 10.0, 500.0]));
+        assert!(rect.contains([5.0, 5.0, 0.0]));
+        assert!(!rect.contains([10.1, 5.0, 0.0]));
+
+        let circle = Region::Circle {
+            center: [0.0, 0.0],
+            radius: 5.0,
+        };
+        assert!(circle.contains([3.0, 4.0, 0.0]));
+        assert!(!circle.contains([4.0, 4.0, 0.0]));
+    }
+
+    #[test]
+    fn tracker_reports_crossings_once() {
+        let mut tracker = RegionTracker::new(Region::Circle {
+            center: [0.0, 0.0],
+            radius: 10.0,
+        });
+        assert_eq!(tracker.update(1, [20.0, 0.0, 0.0]), None);
+        assert_eq!(
+            tracker.update(1, [5.0, 0.0, 0.0]),
+            Some(RegionChange::Entered)
+        );
+        assert_eq!(tracker.update(1, [6.0, 0.0, 0.0]), None);
+        assert!(tracker.is_inside(1));
+        assert_eq!(
+            tracker.update(1, [20.0, 0.0, 0.0]),
+            Some(RegionChange::Left)
+        );
+        assert_eq!(tracker.update(1, [30.0, 0.0, 0.0]), None);
+        assert!(!tracker.is_inside(1));
+    }
+
+    #[test]
+    fn tracker_forgets_deleted_entities() {
+        let mut tracker = RegionTracker::new(Region::Circle {
+            center: [0.0, 0.0],
+            radius: 10.0,
+        });
+        tracker.update(1, [0.0, 0.0, 0.0]);
+        assert!(tracker.remove(1));
+        assert!(!tracker.remove(1));
+        assert!(!tracker.remove(2));
+        assert_eq!(
+            tracker.update(1, [0.0, 0.0, 0.0]),
+            Some(RegionChange::Entered)
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use haste::entities::{DeltaHeader, Entity};
+use haste::flattenedserializers::FlattenedSerializer;
+use haste::parser::{Context, Visitor};
+use serde::Serialize;
+use strum::VariantArray;
+
+use crate::demo_parser::entity_events::EntityType;
+use crate::demo_parser::error::DemoParseError;
+use crate::demo_parser::hashes::paths;
+
+/// A field read by one of the entity extractors, named after its constant in `hashes.rs`.
+struct KnownField {
+    constant: &'static str,
+    path: &'static [&'static str],
+}
+
+/// The paths are taken from `hashes.rs`, a field declared there but not listed in any group
+/// below leaves its path unused and fails the build.
+macro_rules! known_fields {
+    ($($constant:ident),* $(,)?) => {
+        &[$(KnownField { constant: stringify!($constant), path: paths::$constant }),*]
+    };
+}
+
+const POSITION_FIELDS: &[KnownField] = known_fields![CX, CY, CZ, VX, VY, VZ,];
+
+const GAME_RULES_FIELDS: &[KnownField] = known_fields![
+    START_TIME_HASH,
+    PAUSED_HASH,
+    PAUSE_START_TICK_HASH,
+    PAUSE_TEAM_HASH,
+    PAUSED_TICKS_HASH,
+    WINNING_TEAM_HASH,
+];
+
+const PLAYER_CONTROLLER_FIELDS: &[KnownField] = known_fields![
+    PAWN_HASH,
+    STEAM_ID_HASH,
+    STEAM_NAME_HASH,
+    TEAM_HASH,
+    HERO_ID_HASH,
+    HERO_BADGE_XP_HASH,
+    PLAYER_SLOT_HASH,
+    RANK_HASH,
+    ASSIGNED_LANE_HASH,
+    ORIGINAL_ASSIGNED_LANE_HASH,
+    NET_WORTH_HASH,
+    HEALTH_REGEN_HASH,
+    ULTIMATE_TRAINED_HASH,
+    KILLS_HASH,
+    ASSISTS_HASH,
+    DEATHS_HASH,
+    DENIES_HASH,
+    LAST_HITS_HASH,
+    HERO_HEALING_HASH,
+    SELF_HEALING_HASH,
+    HERO_DAMAGE_HASH,
+    OBJECTIVE_DAMAGE_HASH,
+    ULTIMATE_COOLDOWN_END_HASH,
+    UPGRADES_HASH,
+    RESPAWN_TIME_HASH,
+];
+
+const PLAYER_PAWN_FIELDS: &[KnownField] = known_fields![
+    CONTROLLER_HASH,
+    TEAM_HASH,
+    HERO_ID_HASH,
+    HERO_BUILD_ID_HASH,
+    HERO_BUILD_SERIALIZED_HASH,
+    QUICKBUY_HASH,
+    QUICKBUY_AUTO_PURCHASE_HASH,
+    QUICKBUY_AUTO_QUUE_BUILD_HASH,
+    LEVEL_HASH,
+    MAX_HEALTH_HASH,
+    HEALTH_HASH,
+    LIFE_STATE_HASH,
+];
+
+const TEAM_FIELDS: &[KnownField] =
+    known_fields![TEAM_HASH, SCORE_HASH, TEAMNAME_HASH, FLEX_UNLOCKED_HASH,];
+
+const NPC_FIELDS: &[KnownField] = known_fields![
+    HEALTH_HASH,
+    MAX_HEALTH_HASH,
+    CREATE_TIME_HASH,
+    LANE_HASH,
+    SHIELD_ACTIVE_HASH,
+    TEAM_HASH,
+];
+
+const DESTROYABLE_BUILDING_FIELDS: &[KnownField] =
+    known_fields![HEALTH_HASH, MAX_HEALTH_HASH, TEAM_HASH,];
+
+const SINNERS_SACRIFICE_FIELDS: &[KnownField] = known_fields![HEALTH_HASH, MAX_HEALTH_HASH,];
+
+const ABILITY_MELEE_PARRY_FIELDS: &[KnownField] = known_fields![
+    OWNER_ENTITY_HASH,
+    ATTACK_PARRIED_HASH,
+    PARRY_START_TIME_HASH,
+    PARRY_SUCCESS_TIME_HASH,
+];
+
+const ABILITY_FIELDS: &[KnownField] = known_fields![
+    OWNER_ENTITY_HASH,
+    SUBCLASS_ID_HASH,
+    UPGRADE_BITS_HASH,
+    COOLDOWN_START_HASH,
+    COOLDOWN_END_HASH,
+    REMAINING_CHARGES_HASH,
+    CHANNELING_HASH,
+];
+
+const SUBCLASS_FIELDS: &[KnownField] = known_fields![SUBCLASS_ID_HASH,];
+
+const POSITION_ACTIVE_FIELDS: &[KnownField] = known_fields![ACTIVE_HASH,];
+
+/// The fields read by the extractor of `entity_type`, grouped as they are in `entity_events.rs`.
+fn known_fields(entity_type: EntityType) -> Vec<&'static KnownField> {
+    let groups: &[&[KnownField]] = match entity_type {
+        EntityType::GameRulesProxy => &[GAME_RULES_FIELDS],
+        EntityType::PlayerController => &[PLAYER_CONTROLLER_FIELDS],
+        EntityType::PlayerPawn => &[PLAYER_PAWN_FIELDS, POSITION_FIELDS],
+        EntityType::Team => &[TEAM_FIELDS],
+        EntityType::MidBoss
+        | EntityType::TrooperNeutral
+        | EntityType::Trooper
+        | EntityType::TrooperBoss
+        | EntityType::ShieldedSentry
+        | EntityType::BaseDefenseSentry
+        | EntityType::TrooperBarrackBoss
+        | EntityType::BossTier2
+        | EntityType::BossTier3 => &[NPC_FIELDS, POSITION_FIELDS],
+        EntityType::BreakableProp => &[POSITION_FIELDS],
+        EntityType::PunchablePowerup => &[SUBCLASS_FIELDS, POSITION_FIELDS],
+        EntityType::BreakablePropModifierPickup | EntityType::BreakablePropGoldPickup => {
+            &[POSITION_ACTIVE_FIELDS, POSITION_FIELDS]
+        }
+        EntityType::DestroyableBuilding => &[DESTROYABLE_BUILDING_FIELDS, POSITION_FIELDS],
+        EntityType::SinnersSacrifice => &[SINNERS_SACRIFICE_FIELDS, POSITION_FIELDS],
+        EntityType::AbilityMeleeParry => &[ABILITY_MELEE_PARRY_FIELDS],
+        EntityType::Ability => &[ABILITY_FIELDS],
+    };
+    groups.iter().flat_map(|g| g.iter()).collect()
+}
+
+/// Field paths of a serializer, including the fields of nested serializers one level deep.
+fn field_paths(serializer: &FlattenedSerializer) -> BTreeSet<String> {
+    let mut paths = BTreeSet::new();
+    for field in &serializer.fields {
+        let name = &*field.var_name.str;
+        paths.insert(name.to_owned());
+        if let Some(field_serializer) = field.field_serializer.as_ref() {
+            for child in &field_serializer.fields {
+                paths.insert(format!("{name}.{}", &*child.var_name.str));
+            }
+        }
+    }
+    paths
+}
+
+#[derive(Serialize, Debug)]
+struct MissingField {
+    constant: &'static str,
+    path: String,
+}
+
+#[derive(Serialize, Debug)]
+struct EntityTypeSchema {
+    class: String,
+    /// Fields read by the extractor that no longer exist on the class.
+    missing_fields: Vec<MissingField>,
+    /// Fields on the class that are not read by the extractor.
+    unknown_fields: Vec<String>,
+}
+
+impl EntityTypeSchema {
+    fn inspect(entity_type: EntityType, serializer: &FlattenedSerializer) -> Self {
+        let paths = field_paths(serializer);
+        let known_fields = known_fields(entity_type);
+        let known_paths: BTreeSet<String> = known_fields.iter().map(|f| f.path.join(".")).collect();
+        // Only report unknown fields at the top level or inside structs we already read from
+        let known_parents: BTreeSet<&str> = known_fields
+            .iter()
+            .filter(|f| f.path.len() > 1)
+            .map(|f| f.path[0])
+            .collect();
+
+        Self {
+            class: serializer.serializer_name.str.to_string(),
+            missing_fields: known_fields
+                .iter()
+                .filter(|f| !paths.contains(&f.path.join(".")))
+                .map(|f| MissingField {
+                    constant: f.constant,
+                    path: f.path.join("."),
+                })
+                .collect(),
+            unknown_fields: paths
+                .iter()
+                .filter(|p| !known_paths.contains(*p) && !known_parents.contains(p.as_str()))
+                .filter(|p| {
+                    p.split_once('.')
+                        .is_none_or(|(parent, _)| known_parents.contains(parent))
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+pub(crate) struct SchemaReport {
+    entity_types: BTreeMap<String, EntityTypeSchema>,
+    unseen_entity_types: Vec<EntityType>,
+}
+
+impl SchemaReport {
+    pub(crate) fn is_complete(&self) -> bool {
+        self.entity_types.len() == EntityType::VARIANTS.len()
+    }
+
+    pub(crate) fn finish(mut self) -> Self {
+        self.unseen_entity_types = EntityType::VARIANTS
+            .iter()
+            .filter(|e| !self.entity_types.contains_key(&e.to_string()))
+            .copied()
+            .collect();
+        self
+    }
+}
+
+/// Inspects the flattened serializer of the first entity of every [`EntityType`] in the stream.
+#[derive(Default)]
+pub(crate) struct SchemaVisitor {
+    report: Arc<Mutex<SchemaReport>>,
+}
+
+impl SchemaVisitor {
+    pub(crate) fn report(&self) -> Arc<Mutex<SchemaReport>> {
+        self.report.clone()
+    }
+}
+
+impl Visitor for SchemaVisitor {
+    type Error = DemoParseError;
+
+    async fn on_entity(
+        &mut self,
+        _ctx: &Context,
+        _delta_header: DeltaHeader,
+        entity: &Entity,
+    ) -> Result<(), Self::Error> {
+        let Some(entity_type) = EntityType::from_opt(entity) else {
+            return Ok(());
+        };
+        self.report
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entity_types
+            .entry(entity_type.to_string())
+            .or_insert_with(|| EntityTypeSchema::inspect(entity_type, entity.serializer()));
+        Ok(())
+    }
+}
@@ -1,12 +1,14 @@
 use core::fmt::{Display, Formatter};
 
-use axum::response::sse::Event;
 use haste::entities::DeltaHeader;
 use serde::Serialize;
 use strum::{Display, FromRepr};
 use valveprotos::deadlock::CCitadelUserMsgHeroKilled;
 
-use crate::demo_parser::entity_events::{EntityType, EntityUpdateEvents};
+use crate::demo_parser::damage::DamageParticipant;
+use crate::demo_parser::entity_events::{EntityType, EntityUpdateEvents, PlayerControllerEvent};
+use crate::demo_parser::modifiers::ActiveModifier;
+use crate::demo_parser::urn::UrnCarrier;
 
 #[derive(Serialize, Debug, Clone)]
 pub(crate) struct DemoEvent {
@@ -17,15 +19,6 @@ pub(crate) struct DemoEvent {
     pub(super) event: DemoEventPayload,
 }
 
-impl TryInto<Event> for DemoEvent {
-    type Error = axum::Error;
-
-    fn try_into(self) -> Result<Event, Self::Error> {
-        let event = self.event.to_string();
-        Event::default().event(event).json_data(self)
-    }
-}
-
 #[derive(Serialize, Debug, Clone)]
 #[serde(tag = "event_type")]
 #[serde(rename_all = "snake_case")]
@@ -37,14 +30,170 @@ pub(super) enum DemoEventPayload {
         #[serde(flatten)]
         entity_update: EntityUpdateEvents,
     },
+    EntityEnteredRegion {
+        entity_index: i32,
+        entity_type: EntityType,
+        position: [f32; 3],
+    },
+    EntityLeftRegion {
+        entity_index: i32,
+        entity_type: EntityType,
+        position: [f32; 3],
+    },
     ChatMessage {
         steam_name: Option<String>,
         steam_id: Option<u32>,
         text: Option<String>,
         all_chat: Option<bool>,
+        team: Option<u8>,
         lane_color: Option<i32>,
     },
     HeroKilled(CCitadelUserMsgHeroKilled),
+    MapPing {
+        controller: Option<i32>,
+        steam_id: Option<u32>,
+        steam_name: Option<String>,
+        hero_id: Option<u32>,
+        team: Option<u8>,
+        /// What the ping means, e.g. attack, retreat or an enemy sighting.
+        ping_type: Option<i32>,
+        /// Raw `event_type` of the ping message, renamed as `event_type` names the event.
+        ping_event_type: Option<i32>,
+        /// Entity the ping points at, if any.
+        entity_index: Option<i32>,
+        /// Whether the ping was placed on the minimap rather than in the world.
+        minimap: Option<bool>,
+        position: Option<[f32; 3]>,
+    },
+    GamePaused {
+        pause_team: Option<i32>,
+    },
+    GameResumed {
+        pause_team: Option<i32>,
+        /// Length of the pause in seconds.
+        pause_duration: f32,
+    },
+    PlayerDied {
+        pawn: i32,
+        controller: Option<i32>,
+        steam_id: Option<u32>,
+        hero_id: Option<u32>,
+        /// Server time at which the player respawns.
+        respawn_time: Option<f32>,
+        /// Seconds until the player respawns.
+        respawn_in: Option<f32>,
+    },
+    PlayerRespawned {
+        pawn: i32,
+        controller: Option<i32>,
+        steam_id: Option<u32>,
+        hero_id: Option<u32>,
+    },
+    PlayerLevelUp {
+        pawn: i32,
+        controller: Option<i32>,
+        steam_id: Option<u32>,
+        hero_id: Option<u32>,
+        old_level: i32,
+        new_level: i32,
+    },
+    AbilityUpgraded {
+        entity_index: i32,
+        pawn: Option<i32>,
+        controller: Option<i32>,
+        steam_id: Option<u32>,
+        hero_id: Option<u32>,
+        ability_class: String,
+        ability_id: Option<u32>,
+        upgrade_bits: u32,
+        /// Number of trained tiers, the count of set upgrade bits.
+        old_tier: u32,
+        new_tier: u32,
+    },
+    ModifierAdded {
+        controller: Option<i32>,
+        steam_id: Option<u32>,
+        #[serde(flatten)]
+        modifier: ActiveModifier,
+    },
+    ModifierRemoved {
+        controller: Option<i32>,
+        steam_id: Option<u32>,
+        #[serde(flatten)]
+        modifier: ActiveModifier,
+    },
+    UrnSpawned {
+        entity_index: i32,
+        position: Option<[f32; 3]>,
+    },
+    UrnPickedUp {
+        #[serde(flatten)]
+        carrier: UrnCarrier,
+        position: Option<[f32; 3]>,
+    },
+    UrnDropped {
+        #[serde(flatten)]
+        carrier: UrnCarrier,
+        position: Option<[f32; 3]>,
+    },
+    UrnDelivered {
+        #[serde(flatten)]
+        carrier: UrnCarrier,
+    },
+    Damage {
+        attacker: DamageParticipant,
+        victim: DamageParticipant,
+        damage: Option<i32>,
+        /// Damage before resistances and shields.
+        pre_damage: Option<i32>,
+        /// Ability or item entity that dealt the damage.
+        ability: Option<i32>,
+        ability_id: Option<u32>,
+        victim_health: Option<i32>,
+        victim_max_health: Option<i32>,
+        crit: bool,
+        flags: u64,
+    },
+    UserMessage {
+        /// Name of the message in `CitadelUserMessageIds`, e.g. `k_EUserMsg_HeroKilled`.
+        message_type: &'static str,
+        message_id: u32,
+        message: serde_json::Value,
+    },
+    MidBossSpawned {
+        entity_index: i32,
+        /// Game time at which the mid boss spawned.
+        spawn_time: Option<f32>,
+        position: Option<[f32; 3]>,
+    },
+    MidBossKilled {
+        entity_index: i32,
+        securing_team: Option<u8>,
+        /// Entity that landed the last hit, a player pawn or an NPC.
+        killer: Option<i32>,
+        pawn: Option<i32>,
+        controller: Option<i32>,
+        steam_id: Option<u32>,
+        hero_id: Option<u32>,
+        /// Game time at which the mid boss spawned.
+        spawn_time: Option<f32>,
+        /// Seconds the mid boss was alive for.
+        alive_duration: Option<f32>,
+        /// Game time at which the next mid boss spawns.
+        respawn_time: f32,
+    },
+    RejuvPickedUp {
+        pawn: Option<i32>,
+        controller: Option<i32>,
+        steam_id: Option<u32>,
+        hero_id: Option<u32>,
+        team: Option<u32>,
+    },
+    MatchEnded {
+        winning_team: Option<u8>,
+        duration: f32,
+        players: Vec<PlayerControllerEvent>,
+    },
     TickEnd,
 }
 
@@ -54,8 +203,29 @@ impl Display for DemoEventPayload {
             Self::EntityUpdate {
                 delta, entity_type, ..
             } => write!(f, "{entity_type}_entity_{delta}"),
+            Self::EntityEnteredRegion { .. } => write!(f, "entity_entered_region"),
+            Self::EntityLeftRegion { .. } => write!(f, "entity_left_region"),
             Self::ChatMessage { .. } => write!(f, "chat_message"),
             Self::HeroKilled { .. } => write!(f, "hero_killed"),
+            Self::MapPing { .. } => write!(f, "map_ping"),
+            Self::GamePaused { .. } => write!(f, "game_paused"),
+            Self::GameResumed { .. } => write!(f, "game_resumed"),
+            Self::PlayerDied { .. } => write!(f, "player_died"),
+            Self::PlayerRespawned { .. } => write!(f, "player_respawned"),
+            Self::PlayerLevelUp { .. } => write!(f, "player_level_up"),
+            Self::AbilityUpgraded { .. } => write!(f, "ability_upgraded"),
+            Self::ModifierAdded { .. } => write!(f, "modifier_added"),
+            Self::ModifierRemoved { .. } => write!(f, "modifier_removed"),
+            Self::UrnSpawned { .. } => write!(f, "urn_spawned"),
+            Self::UrnPickedUp { .. } => write!(f, "urn_picked_up"),
+            Self::UrnDropped { .. } => write!(f, "urn_dropped"),
+            Self::UrnDelivered { .. } => write!(f, "urn_delivered"),
+            Self::Damage { .. } => write!(f, "damage"),
+            Self::UserMessage { .. } => write!(f, "user_message"),
+            Self::MidBossSpawned { .. } => write!(f, "mid_boss_spawned"),
+            Self::MidBossKilled { .. } => write!(f, "mid_boss_killed"),
+            Self::RejuvPickedUp { .. } => write!(f, "rejuv_picked_up"),
+            Self::MatchEnded { .. } => write!(f, "match_ended"),
             Self::TickEnd => write!(f, "tick_end"),
         }
     }
@@ -0,0 +1,213 @@
+use serde::Serialize;
+
+use crate::demo_parser::utils::subclass_id;
+
+/// Subclass of the `PunchablePowerup` that is the urn, the other punchable powerups are not
+/// tracked.
+pub(super) const URN_SUBCLASS_ID: u32 = subclass_id(b"citadel_idol");
+/// Modifier applied to the player carrying the urn.
+pub(super) const URN_CARRIER_MODIFIER_ID: u32 = subclass_id(b"modifier_citadel_idol_carrier");
+
+/// Whether a modifier is the urn carrier modifier, modifier ids are read as `i32`.
+#[allow(clippy::cast_sign_loss)]
+pub(super) fn is_urn_carrier_modifier(modifier_id: Option<i32>) -> bool {
+    modifier_id.is_some_and(|id| id as u32 == URN_CARRIER_MODIFIER_ID)
+}
+
+/// The player carrying the urn.
+#[derive(Serialize, Debug, Clone)]
+pub(super) struct UrnCarrier {
+    pub(super) pawn: i32,
+    pub(super) controller: Option<i32>,
+    pub(super) steam_id: Option<u32>,
+    pub(super) team: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum UrnChange {
+    Spawned {
+        entity_index: i32,
+        position: Option<[f32; 3]>,
+    },
+    PickedUp {
+        pawn: i32,
+        /// Last known position of the urn on the map.
+        position: Option<[f32; 3]>,
+    },
+    Dropped {
+        pawn: i32,
+    },
+    Delivered {
+        pawn: i32,
+    },
+}
+
+/// Follows the urn from its spawn to its delivery.
+///
+/// The urn is an entity while it lies on the map. Once it is picked up the entity is removed and
+/// the carrier is the player with the urn carrier modifier. When the modifier is removed without
+/// a delivery the urn was dropped, and the entity that reappears on the map is the same urn.
+#[derive(Debug, Default)]
+pub(super) struct UrnTracker {
+    /// Entity index and last known position of the urn while it lies on the map.
+    on_map: Option<(i32, Option<[f32; 3]>)>,
+    /// Last known position of the urn entity removed from the map, until it is picked up.
+    removed_at: Option<[f32; 3]>,
+    /// Pawn carrying the urn.
+    carrier: Option<i32>,
+    /// Whether the urn was dropped and is about to reappear on the map.
+    dropped: bool,
+}
+
+impl UrnTracker {
+    /// Records the position of the urn entity, a new entity is a new urn unless it was dropped.
+    pub(super) fn update(
+        &mut self,
+        entity_index: i32,
+        position: Option<[f32; 3]>,
+    ) -> Option<UrnChange> {
+        if let Some((index, last_position)) = &mut self.on_map
+            && *index == entity_index
+        {
+            if position.is_some() {
+                *last_position = position;
+            }
+            return None;
+        }
+        self.on_map = Some((entity_index, position));
+        if core::mem::take(&mut self.dropped) {
+            return None;
+        }
+        Some(UrnChange::Spawned {
+            entity_index,
+            position,
+        })
+    }
+
+    /// Forgets the urn entity once it is removed from the map.
+    pub(super) fn remove(&mut self, entity_index: i32) {
+        if let Some((index, position)) = self.on_map
+            && index == entity_index
+        {
+            self.on_map = None;
+            self.removed_at = position;
+        }
+    }
+
+    /// A pawn received the urn carrier modifier.
+    pub(super) fn carrier_added(&mut self, pawn: i32) -> Option<UrnChange> {
+        if self.carrier == Some(pawn) {
+            return None;
+        }
+        self.carrier = Some(pawn);
+        self.dropped = false;
+        let removed_at = self.removed_at.take();
+        let position = self
+            .on_map
+            .take()
+            .and_then(|(_, position)| position)
+            .or(removed_at);
+        Some(UrnChange::PickedUp { pawn, position })
+    }
+
+    /// A pawn lost the urn carrier modifier, which is a drop if the urn was not delivered.
+    pub(super) fn carrier_removed(&mut self, pawn: i32) -> Option<UrnChange> {
+        if self.carrier != Some(pawn) {
+            return None;
+        }
+        self.carrier = None;
+        self.dropped = true;
+        Some(UrnChange::Dropped { pawn })
+    }
+
+    /// The carrier returned the urn.
+    pub(super) fn delivered(&mut self) -> Option<UrnChange> {
+        let pawn = self.carrier.take()?;
+        self.dropped = false;
+        Some(UrnChange::Delivered { pawn })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITION: Option<[f32; 3]> = Some([100.0, 200.0, 0.0]);
+
+    #[test]
+    fn urn_spawns_once() {
+        let mut tracker = UrnTracker::default();
+        assert_eq!(
+            tracker.update(10, POSITION),
+            Some(UrnChange::Spawned {
+                entity_index: 10,
+                position: POSITION
+            })
+        );
+        assert_eq!(tracker.update(10, None), None);
+        assert_eq!(tracker.update(10, Some([0.0; 3])), None);
+    }
+
+    #[test]
+    fn pickup_reports_the_last_position() {
+        let mut tracker = UrnTracker::default();
+        tracker.update(10, POSITION);
+        tracker.update(10, None);
+        tracker.remove(10);
+        assert_eq!(
+            tracker.carrier_added(1),
+            Some(UrnChange::PickedUp {
+                pawn: 1,
+                position: POSITION
+            })
+        );
+        assert_eq!(tracker.carrier_added(1), None);
+    }
+
+    #[test]
+    fn drop_without_death_does_not_respawn() {
+        let mut tracker = UrnTracker::default();
+        tracker.update(10, POSITION);
+        tracker.remove(10);
+        tracker.carrier_added(1);
+        assert_eq!(
+            tracker.carrier_removed(1),
+            Some(UrnChange::Dropped { pawn: 1 })
+        );
+        assert_eq!(tracker.update(11, POSITION), None);
+        tracker.remove(11);
+        assert_eq!(
+            tracker.carrier_added(2),
+            Some(UrnChange::PickedUp {
+                pawn: 2,
+                position: POSITION
+            })
+        );
+    }
+
+    #[test]
+    fn delivery_is_not_a_drop() {
+        let mut tracker = UrnTracker::default();
+        tracker.update(10, POSITION);
+        tracker.remove(10);
+        tracker.carrier_added(1);
+        assert_eq!(tracker.delivered(), Some(UrnChange::Delivered { pawn: 1 }));
+        assert_eq!(tracker.carrier_removed(1), None);
+        assert_eq!(tracker.delivered(), None);
+        assert!(matches!(
+            tracker.update(12, POSITION),
+            Some(UrnChange::Spawned {
+                entity_index: 12,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn modifier_of_other_pawns_is_ignored() {
+        let mut tracker = UrnTracker::default();
+        tracker.carrier_added(1);
+        assert_eq!(tracker.carrier_removed(2), None);
+        assert_eq!(tracker.delivered(), Some(UrnChange::Delivered { pawn: 1 }));
+    }
+}
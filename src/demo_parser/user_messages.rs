@@ -0,0 +1,113 @@
+use core::str::FromStr;
+
+use prost::Message;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[allow(clippy::wildcard_imports)]
+use valveprotos::deadlock::*;
+
+/// Prefix of the user message names, optional in subscriptions.
+const NAME_PREFIX: &str = "k_EUserMsg_";
+
+/// Maps every `CitadelUserMessageIds` that has a protobuf message in valveprotos onto it, ids
+/// without a message cannot be decoded and are not subscribable.
+///
+/// Chat, pings, chat wheel and map drawings are team communication and left out, they are only
+/// sent through `chat_message` and `map_ping` which apply the chat and player filters.
+macro_rules! user_messages {
+    ($($id:ident => $message:ty),* $(,)?) => {
+        fn is_supported(id: CitadelUserMessageIds) -> bool {
+            matches!(id, $(CitadelUserMessageIds::$id)|*)
+        }
+
+        /// Decodes a user message into JSON, `None` if it is not supported or malformed.
+        pub(super) fn decode(id: CitadelUserMessageIds, data: &[u8]) -> Option<serde_json::Value> {
+            match id {
+                $(CitadelUserMessageIds::$id => serde_json::to_value(<$message>::decode(data).ok()?).ok(),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+user_messages! {
+    KEUserMsgDamage => CCitadelUserMessageDamage,
+    KEUserMsgTeamRewards => CCitadelUserMsgTeamRewards,
+    KEUserMsgTriggerDamageFlash => CCitadelUserMsgTriggerDamageFlash,
+    KEUserMsgAbilitiesChanged => CCitadelUserMsgAbilitiesChanged,
+    KEUserMsgRecentDamageSummary => CCitadelUserMsgRecentDamageSummary,
+    KEUserMsgSpectatorTeamChanged => CCitadelUserMsgSpectatorTeamChanged,
+    KEUserMsgGoldHistory => CCitadelUserMsgGoldHistory,
+    KEUserMsgPostMatchDetails => CCitadelUserMsgPostMatchDetails,
+    KEUserMsgItemPurchaseNotification => CCitadelUserMsgItemPurchaseNotification,
+    KEUserMsgAbilityFailed => CCitadelUserMsgAbilityFailed,
+    KEUserMsgAbilityLateFailure => CCitadelUserMsgAbilityLateFailure,
+    KEUserMsgPostProcessingAnim => CCitadelUserMsgPostProcessingAnim,
+    KEUserMsgDeathReplayData => CCitadelUserMsgDeathReplayData,
+    KEUserMsgPlayerLifetimeStatInfo => CCitadelUserMsgPlayerLifetimeStatInfo,
+    KEUserMsgForceShopClosed => CCitadelUserMsgForceShopClosed,
+    KEUserMsgStaminaDrained => CCitadelUserMsgStaminaDrained,
+    KEUserMsgAbilityNotify => CCitadelUserMsgAbilityNotify,
+    KEUserMsgGetDamageStatsResponse => CCitadelUserMsgGetDamageStatsResponse,
+    KEUserMsgParticipantStartSoundEvent => CCitadelUserMsgParticipantStartSoundEvent,
+    KEUserMsgParticipantStopSoundEvent => CCitadelUserMsgParticipantStopSoundEvent,
+    KEUserMsgParticipantStopSoundEventHash => CCitadelUserMsgParticipantStopSoundEventHash,
+    KEUserMsgParticipantSetSoundEventParams => CCitadelUserMsgParticipantSetSoundEventParams,
+    KEUserMsgParticipantSetLibraryStackFields => CCitadelUserMsgParticipantSetLibraryStackFields,
+    KEUserMsgCurrencyChanged => CCitadelUserMsgCurrencyChanged,
+    KEUserMsgGameOver => CCitadelUserMsgGameOver,
+    KEUserMsgBossKilled => CCitadelUserMsgBossKilled,
+    KEUserMsgBossDamaged => CCitadelUserMsgBossDamaged,
+    KEUserMsgReturnIdol => CCitadelUserMsgReturnIdol,
+    KEUserMsgSetClientCameraAngles => CCitadelUserMsgSetClientCameraAngles,
+    KEUserMsgBulletHit => CCitadelUserMsgBulletHit,
+    KEUserMsgObjectiveMask => CCitadelUserMsgObjectiveMask,
+    KEUserMsgModifierApplied => CCitadelUserMsgModifierApplied,
+    KEUserMsgCameraController => CCitadelUserMsgCameraController,
+    KEUserMsgAuraModifierApplied => CCitadelUserMsgAuraModifierApplied,
+    KEUserMsgObstructedShotFired => CCitadelUserMsgObstructedShotFired,
+    KEUserMsgHeroKilled => CCitadelUserMsgHeroKilled,
+    KEUserMsgImportantAbilityUsed => CCitadelUserMsgImportantAbilityUsed,
+    KEUserMsgRejuvStatus => CCitadelUserMsgRejuvStatus,
+    KEUserMsgKillStreak => CCitadelUserMsgKillStreak,
+    KEUserMsgPlayerRespawned => CCitadelUserMsgPlayerRespawned,
+    KEUserMsgCallCheaterVote => CCitadelUserMsgCallCheaterVote,
+    KEUserMsgMeleeHit => CCitadelUserMsgMeleeHit,
+    KEUserMsgFlexSlotUnlocked => CCitadelUserMsgFlexSlotUnlocked,
+    KEUserMsgSeasonalKill => CCitadelUserMsgSeasonalKill,
+    KEUserMsgMusicQueue => CCitadelUserMsgMusicQueue,
+}
+
+/// A user message that can be subscribed to, parsed from its `CitadelUserMessageIds` name,
+/// e.g. `k_EUserMsg_HeroKilled` or `HeroKilled`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct UserMessageId(pub(crate) CitadelUserMessageIds);
+
+impl FromStr for UserMessageId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = if s.starts_with(NAME_PREFIX) {
+            s.to_string()
+        } else {
+            format!("{NAME_PREFIX}{s}")
+        };
+        CitadelUserMessageIds::from_str_name(&name)
+            .filter(|&id| is_supported(id))
+            .map(Self)
+            .ok_or_else(|| format!("Unsupported user message: {s}"))
+    }
+}
+
+impl<'de> Deserialize<'de> for UserMessageId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for UserMessageId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str_name())
+    }
+}
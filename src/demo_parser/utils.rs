@@ -1,7 +1,9 @@
-use haste::entities::{Entity, deadlock_coord_from_cell};
+use haste::entities::{Entity, deadlock_coord_from_cell, ehandle_to_index};
+use haste::parser::Context;
 
 #[allow(clippy::wildcard_imports)]
 use crate::demo_parser::hashes::*;
+use crate::utils::steamid64_to_steamid3;
 
 fn get_entity_coord(entity: &Entity, cell_key: u64, vec_key: u64) -> Option<f32> {
     deadlock_coord_from_cell(entity.get_value(&cell_key)?, entity.get_value(&vec_key)?).into()
@@ -15,3 +17,48 @@ pub(super) fn get_entity_position(entity: &Entity) -> Option<[f32; 3]> {
     ]
     .into()
 }
+
+/// Controller index and steam id of the player owning the pawn at `pawn_index`.
+pub(super) fn get_pawn_player(ctx: &Context, pawn_index: i32) -> (Option<i32>, Option<u32>) {
+    let entities = ctx.entities();
+    let controller = entities
+        .and_then(|e| e.get(&pawn_index))
+        .and_then(|pawn| pawn.get_value(&CONTROLLER_HASH))
+        .map(ehandle_to_index);
+    let steam_id = controller
+        .and_then(|c| entities?.get(&c))
+        .and_then(|c| c.get_value(&STEAM_ID_HASH))
+        .and_then(|s| steamid64_to_steamid3(s).ok());
+    (controller, steam_id)
+}
+
+/// Id of a vdata subclass as found in `m_nSubclassID` and modifier table entries, the 32 bit
+/// `MurmurHash2` of the lowercase subclass name seeded with `0x31415926`.
+#[allow(clippy::cast_possible_truncation, clippy::cast_lossless)]
+pub(super) const fn subclass_id(name: &[u8]) -> u32 {
+    const M: u32 = 0x5bd1_e995;
+    let mut hash = 0x3141_5926 ^ name.len() as u32;
+    let mut i = 0;
+    while i + 4 <= name.len() {
+        let mut k = u32::from_le_bytes([name[i], name[i + 1], name[i + 2], name[i + 3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> 24;
+        k = k.wrapping_mul(M);
+        hash = hash.wrapping_mul(M) ^ k;
+        i += 4;
+    }
+    let rest = name.len() - i;
+    if rest == 3 {
+        hash ^= (name[i + 2] as u32) << 16;
+    }
+    if rest >= 2 {
+        hash ^= (name[i + 1] as u32) << 8;
+    }
+    if rest >= 1 {
+        hash ^= name[i] as u32;
+        hash = hash.wrapping_mul(M);
+    }
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^ (hash >> 15)
+}
@@ -1,8 +1,9 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 
 use axum::response::sse::Event;
 use haste::demostream::CmdHeader;
-use haste::entities::{DeltaHeader, Entity};
+use haste::entities::{DeltaHeader, Entity, ehandle_to_index};
 use haste::parser::{Context, Visitor};
 use haste::stringtables::StringTableItem;
 use prost::Message;
@@ -10,39 +11,801 @@ use tokio::sync::mpsc::UnboundedSender;
 use tracing::debug;
 use valveprotos::common::{CMsgPlayerInfo, EDemoCommands};
 use valveprotos::deadlock::{
-    CCitadelUserMsgChatMsg, CCitadelUserMsgHeroKilled, CitadelUserMessageIds,
+    CCitadelUserMessageDamage, CCitadelUserMsgBossKilled, CCitadelUserMsgChatMsg,
+    CCitadelUserMsgHeroKilled, CCitadelUserMsgMapPing, CCitadelUserMsgRejuvStatus,
+    CitadelUserMessageIds,
 };
 
+use crate::demo_parser::chat::ChatFilter;
+use crate::demo_parser::damage::{CRIT_DAMAGE_FLAG, DamageParticipant};
 use crate::demo_parser::entity_events::{
-    EntityType, EntityUpdateEvent, EntityUpdateEvents, GameRulesProxyEvent,
+    EntityType, EntityUpdateEvent, EntityUpdateEvents, GameRulesProxyEvent, PlayerControllerEvent,
 };
 use crate::demo_parser::error::DemoParseError;
+use crate::demo_parser::hashes::{
+    CONTROLLER_HASH, CREATE_TIME_HASH, LEVEL_HASH, LIFE_STATE_HASH, OWNER_ENTITY_HASH,
+    SUBCLASS_ID_HASH, TEAM_HASH, UPGRADE_BITS_HASH,
+};
+use crate::demo_parser::mid_boss::MidBossTracker;
+use crate::demo_parser::minimap::Minimap;
+use crate::demo_parser::modifiers::{ModifierChange, ModifierTracker};
+use crate::demo_parser::region::{Region, RegionChange, RegionTracker};
 use crate::demo_parser::types::{DemoEvent, DemoEventPayload};
+use crate::demo_parser::urn::{
+    URN_SUBCLASS_ID, UrnCarrier, UrnChange, UrnTracker, is_urn_carrier_modifier,
+};
+use crate::demo_parser::{user_messages, utils};
+use crate::streams::StreamProgress;
+use crate::telemetry;
 use crate::utils::steamid64_to_steamid3;
 
+/// Optional events a client subscribed to.
+#[derive(Debug, Default)]
+pub(crate) struct Subscriptions {
+    pub(crate) chat_messages: bool,
+    pub(crate) chat_filter: ChatFilter,
+    /// Map pings are team communication, they pass the chat filter like team chat messages.
+    pub(crate) map_pings: bool,
+    /// Entity types to send updates of, all but [`EntityType::is_default`] ones if unset.
+    pub(crate) entities: Option<HashSet<EntityType>>,
+    pub(crate) modifiers: bool,
+    pub(crate) damage: bool,
+    /// Ids of the user messages to send as `user_message` events.
+    pub(crate) user_messages: HashSet<u32>,
+    /// Adds minimap coordinates to positions if set.
+    pub(crate) minimap: Option<Minimap>,
+    /// Only send updates of entities inside this region, entities without a position are
+    /// always sent.
+    pub(crate) region: Option<Region>,
+    /// Only send player events of these players, all if unset.
+    pub(crate) players: Option<PlayerFilter>,
+    /// Seconds between the death of the mid boss and its respawn.
+    pub(crate) mid_boss_respawn_delay: f32,
+}
+
+/// `event_type` of a `RejuvStatus` message when a player claimed the Rejuvenator, the message
+/// is also sent when it spawns or expires.
+const REJUV_PICKED_UP: i32 = 1;
+
+/// Players selected by steam id or hero id.
+#[derive(Debug, Default)]
+pub(crate) struct PlayerFilter {
+    pub(crate) steam_ids: HashSet<u32>,
+    pub(crate) hero_ids: HashSet<u32>,
+}
+
+impl PlayerFilter {
+    /// A player is selected by either their steam id or their hero id.
+    fn matches(&self, player: &PlayerControllerEvent) -> bool {
+        player.steam_id.is_some_and(|s| self.steam_ids.contains(&s))
+            || player.hero_id.is_some_and(|h| self.hero_ids.contains(&h))
+    }
+
+    /// Whether the pawn belongs to a selected player, pawns are resolved through the `pawn` of
+    /// the controllers.
+    fn matches_pawn<'a>(
+        &self,
+        players: impl IntoIterator<Item = &'a PlayerControllerEvent>,
+        pawn: i32,
+    ) -> bool {
+        players
+            .into_iter()
+            .any(|p| p.pawn == Some(pawn) && self.matches(p))
+    }
+}
+
 pub(crate) struct SendingVisitor {
     sender: UnboundedSender<Event>,
-    subscribed_chat_messages: bool,
-    subscribed_entities: Option<HashSet<EntityType>>,
+    progress: Arc<StreamProgress>,
+    subscriptions: Subscriptions,
     game_time: f32,
     tick_interval: f32,
     rules: GameRulesProxyEvent,
+    /// Ticks spent in pauses that have ended, kept ahead of `total_paused_ticks` which may lag behind.
+    completed_paused_ticks: i32,
+    /// Latest state of every player controller by entity index, for the final scoreboard.
+    players: BTreeMap<i32, PlayerControllerEvent>,
+    /// Latest state of every player pawn by entity index.
+    pawns: HashMap<i32, PawnState>,
+    /// Upgrade bits of each ability, by entity index.
+    ability_upgrades: HashMap<i32, u32>,
+    /// Pawns that died or respawned this tick, sent at the end of the tick once their
+    /// controllers are up to date.
+    life_state_changes: Vec<LifeStateChange>,
+    /// Always tracked, the urn carrier is found through its modifier.
+    modifiers: ModifierTracker,
+    /// Only tracked if the client subscribed to a region.
+    region: Option<RegionTracker>,
+    urn: UrnTracker,
+    mid_boss: MidBossTracker,
+    /// Last tick that ended, for the events sent after the broadcast ran out.
+    tick: i32,
+    match_ended: bool,
+}
+
+#[derive(Default)]
+struct PawnState {
+    controller: Option<i32>,
+    team: Option<u8>,
+    alive: Option<bool>,
+    level: Option<i32>,
+    position: Option<[f32; 3]>,
+}
+
+struct LifeStateChange {
+    pawn: i32,
+    controller: Option<i32>,
+    alive: bool,
 }
 
 impl SendingVisitor {
     pub(crate) fn new(
         sender: UnboundedSender<Event>,
-        subscribed_chat_messages: bool,
-        subscribed_entities: Option<impl IntoIterator<Item = EntityType>>,
+        progress: Arc<StreamProgress>,
+        subscriptions: Subscriptions,
     ) -> Self {
+        let mid_boss_respawn_delay = subscriptions.mid_boss_respawn_delay;
         Self {
             sender,
-            subscribed_chat_messages,
-            subscribed_entities: subscribed_entities.map(|iter| iter.into_iter().collect()),
+            progress,
+            modifiers: ModifierTracker::default(),
+            region: subscriptions.region.map(RegionTracker::new),
+            subscriptions,
+            urn: UrnTracker::default(),
+            mid_boss: MidBossTracker::new(mid_boss_respawn_delay),
             game_time: 0.0,
             tick_interval: 1.0 / 60.0,
             rules: GameRulesProxyEvent::default(),
+            completed_paused_ticks: 0,
+            players: BTreeMap::new(),
+            pawns: HashMap::new(),
+            ability_upgrades: HashMap::new(),
+            life_state_changes: Vec::new(),
+            tick: 0,
+            match_ended: false,
+        }
+    }
+
+    /// Sends `game_paused` and `game_resumed` events when the pause state in the game rules changes.
+    fn update_pause_state(
+        &mut self,
+        ctx: &Context,
+        rules: &GameRulesProxyEvent,
+    ) -> Result<(), DemoParseError> {
+        let was_paused = self.rules.game_paused.unwrap_or_default();
+        let is_paused = rules.game_paused.unwrap_or_default();
+        match (was_paused, is_paused) {
+            (false, true) => {
+                debug!("Game paused by team {:?}", rules.pause_team);
+                self.send(
+                    ctx,
+                    DemoEventPayload::GamePaused {
+                        pause_team: rules.pause_team,
+                    },
+                )?;
+            }
+            (true, false) => {
+                let pause_start_tick = self.rules.pause_start_tick.unwrap_or(ctx.tick());
+                let paused_ticks = (ctx.tick() - pause_start_tick).max(0);
+                self.completed_paused_ticks = (self.completed_paused_ticks + paused_ticks)
+                    .max(rules.total_paused_ticks.unwrap_or_default());
+                debug!("Game resumed after {paused_ticks} ticks");
+                #[allow(clippy::cast_precision_loss)]
+                let pause_duration = paused_ticks as f32 * self.tick_interval;
+                self.send(
+                    ctx,
+                    DemoEventPayload::GameResumed {
+                        pause_team: self.rules.pause_team,
+                        pause_duration,
+                    },
+                )?;
+            }
+            (false, false) => {
+                self.completed_paused_ticks = self
+                    .completed_paused_ticks
+                    .max(rules.total_paused_ticks.unwrap_or_default());
+            }
+            (true, true) => {}
+        }
+        Ok(())
+    }
+
+    /// Ticks the game has been paused for, including the ongoing pause.
+    fn paused_ticks(&self, tick: i32) -> i32 {
+        let ongoing_pause = match (self.rules.game_paused, self.rules.pause_start_tick) {
+            (Some(true), Some(pause_start_tick)) => (tick - pause_start_tick).max(0),
+            _ => 0,
+        };
+        (self.completed_paused_ticks + ongoing_pause)
+            .max(self.rules.total_paused_ticks.unwrap_or_default())
+    }
+
+    /// Updates the state of a player pawn, the fields of a pawn are only sent when they change.
+    fn track_pawn(&mut self, delta: DeltaHeader, entity: &Entity) {
+        if delta == DeltaHeader::DELETE {
+            self.pawns.remove(&entity.index());
+            return;
+        }
+        let pawn = self.pawns.entry(entity.index()).or_default();
+        if let Some(controller) = entity.get_value(&CONTROLLER_HASH).map(ehandle_to_index) {
+            pawn.controller = Some(controller);
+        }
+        if let Some(team) = entity.get_value(&TEAM_HASH) {
+            pawn.team = Some(team);
+        }
+        if let Some(position) = utils::get_entity_position(entity) {
+            pawn.position = Some(position);
+        }
+    }
+
+    /// Records pawns dying or respawning, pawns are alive while their life state is 0.
+    fn update_life_state(&mut self, entity: &Entity) {
+        let Some(life_state) = entity.get_value::<u8>(&LIFE_STATE_HASH) else {
+            return;
+        };
+        let Some(pawn) = self.pawns.get_mut(&entity.index()) else {
+            return;
+        };
+        let alive = life_state == 0;
+        if pawn
+            .alive
+            .replace(alive)
+            .is_some_and(|was_alive| was_alive != alive)
+        {
+            self.life_state_changes.push(LifeStateChange {
+                pawn: entity.index(),
+                controller: pawn.controller,
+                alive,
+            });
+        }
+    }
+
+    /// Whether the player with the given controller passes the player filter.
+    fn is_player_selected(&self, controller: Option<i32>) -> bool {
+        let Some(filter) = &self.subscriptions.players else {
+            return true;
+        };
+        controller
+            .and_then(|c| self.players.get(&c))
+            .is_some_and(|p| filter.matches(p))
+    }
+
+    /// Whether the player owning the given pawn passes the player filter, pawns are resolved
+    /// through the `pawn` of the controllers.
+    fn is_pawn_selected(&self, pawn: Option<i32>) -> bool {
+        let Some(filter) = &self.subscriptions.players else {
+            return true;
+        };
+        pawn.is_some_and(|pawn| filter.matches_pawn(self.players.values(), pawn))
+    }
+
+    /// Whether updates of the entity pass the player filter, only player and ability entities
+    /// are filtered.
+    fn is_entity_selected(&self, entity_type: EntityType, entity: &Entity) -> bool {
+        match entity_type {
+            EntityType::PlayerController => self.is_player_selected(Some(entity.index())),
+            EntityType::PlayerPawn => self.is_pawn_selected(Some(entity.index())),
+            EntityType::Ability | EntityType::AbilityMeleeParry => {
+                self.is_pawn_selected(entity.get_value(&OWNER_ENTITY_HASH).map(ehandle_to_index))
+            }
+            _ => true,
+        }
+    }
+
+    /// Steam id and hero id of the player with the given controller.
+    fn player_ids(&self, controller: Option<i32>) -> (Option<u32>, Option<u32>) {
+        let player = controller.and_then(|c| self.players.get(&c));
+        (
+            player.and_then(|p| p.steam_id),
+            player.and_then(|p| p.hero_id),
+        )
+    }
+
+    /// Sends `player_level_up` events when a pawn's level increases.
+    fn update_level(&mut self, ctx: &Context, entity: &Entity) -> Result<(), DemoParseError> {
+        let Some(new_level) = entity.get_value::<i32>(&LEVEL_HASH) else {
+            return Ok(());
+        };
+        let Some(pawn) = self.pawns.get_mut(&entity.index()) else {
+            return Ok(());
+        };
+        let Some(old_level) = pawn
+            .level
+            .replace(new_level)
+            .filter(|&old_level| old_level < new_level)
+        else {
+            return Ok(());
+        };
+        let controller = pawn.controller;
+        if !self.is_pawn_selected(Some(entity.index())) {
+            return Ok(());
+        }
+        let (steam_id, hero_id) = self.player_ids(controller);
+        self.send(
+            ctx,
+            DemoEventPayload::PlayerLevelUp {
+                pawn: entity.index(),
+                controller,
+                steam_id,
+                hero_id,
+                old_level,
+                new_level,
+            },
+        )
+    }
+
+    /// Sends `ability_upgraded` events when an ability gains upgrade bits.
+    fn update_ability(
+        &mut self,
+        ctx: &Context,
+        delta: DeltaHeader,
+        entity: &Entity,
+    ) -> Result<(), DemoParseError> {
+        let entity_index = entity.index();
+        if delta == DeltaHeader::DELETE {
+            self.ability_upgrades.remove(&entity_index);
+            return Ok(());
+        }
+        let Some(upgrade_bits) = entity.get_value::<u32>(&UPGRADE_BITS_HASH) else {
+            return Ok(());
+        };
+        let Some(old_bits) = self
+            .ability_upgrades
+            .insert(entity_index, upgrade_bits)
+            .filter(|&old_bits| old_bits.count_ones() < upgrade_bits.count_ones())
+        else {
+            return Ok(());
+        };
+        let pawn = entity.get_value(&OWNER_ENTITY_HASH).map(ehandle_to_index);
+        if !self.is_pawn_selected(pawn) {
+            return Ok(());
         }
+        let controller = pawn
+            .and_then(|p| self.pawns.get(&p))
+            .and_then(|p| p.controller);
+        let (steam_id, hero_id) = self.player_ids(controller);
+        self.send(
+            ctx,
+            DemoEventPayload::AbilityUpgraded {
+                entity_index,
+                pawn,
+                controller,
+                steam_id,
+                hero_id,
+                ability_class: entity.serializer().serializer_name.str.to_string(),
+                ability_id: entity.get_value(&SUBCLASS_ID_HASH),
+                upgrade_bits,
+                old_tier: old_bits.count_ones(),
+                new_tier: upgrade_bits.count_ones(),
+            },
+        )
+    }
+
+    fn urn_carrier(&self, pawn: i32) -> UrnCarrier {
+        let state = self.pawns.get(&pawn);
+        let controller = state.and_then(|p| p.controller);
+        UrnCarrier {
+            pawn,
+            controller,
+            steam_id: self.player_ids(controller).0,
+            team: state.and_then(|p| p.team),
+        }
+    }
+
+    /// Sends `urn_spawned` events as the urn appears on the map, pickups and drops follow the
+    /// urn carrier modifier.
+    fn update_urn(
+        &mut self,
+        ctx: &Context,
+        delta: DeltaHeader,
+        entity: &Entity,
+    ) -> Result<(), DemoParseError> {
+        if entity.get_value::<u32>(&SUBCLASS_ID_HASH) != Some(URN_SUBCLASS_ID) {
+            return Ok(());
+        }
+        if delta == DeltaHeader::DELETE {
+            self.urn.remove(entity.index());
+            return Ok(());
+        }
+        let change = self
+            .urn
+            .update(entity.index(), utils::get_entity_position(entity));
+        self.send_urn_change(ctx, change)
+    }
+
+    /// Sends an urn event, pickups, drops and deliveries only if the carrier passes the player
+    /// filter.
+    fn send_urn_change(
+        &self,
+        ctx: &Context,
+        change: Option<UrnChange>,
+    ) -> Result<(), DemoParseError> {
+        let Some(change) = change else {
+            return Ok(());
+        };
+        let event = match change {
+            UrnChange::Spawned {
+                entity_index,
+                position,
+            } => DemoEventPayload::UrnSpawned {
+                entity_index,
+                position,
+            },
+            UrnChange::PickedUp { pawn, .. }
+            | UrnChange::Dropped { pawn }
+            | UrnChange::Delivered { pawn }
+                if !self.is_pawn_selected(Some(pawn)) =>
+            {
+                return Ok(());
+            }
+            UrnChange::PickedUp { pawn, position } => DemoEventPayload::UrnPickedUp {
+                carrier: self.urn_carrier(pawn),
+                position,
+            },
+            UrnChange::Dropped { pawn } => DemoEventPayload::UrnDropped {
+                carrier: self.urn_carrier(pawn),
+                position: self.pawns.get(&pawn).and_then(|p| p.position),
+            },
+            UrnChange::Delivered { pawn } => DemoEventPayload::UrnDelivered {
+                carrier: self.urn_carrier(pawn),
+            },
+        };
+        self.send(ctx, event)
+    }
+
+    /// Sends `mid_boss_spawned` when a mid boss entity is created, its spawn time is taken from
+    /// the entity so it is also right for mid boss entities created when joining mid-game.
+    fn update_mid_boss(
+        &mut self,
+        ctx: &Context,
+        delta: DeltaHeader,
+        entity: &Entity,
+    ) -> Result<(), DemoParseError> {
+        let entity_index = entity.index();
+        if delta == DeltaHeader::DELETE {
+            self.mid_boss.remove(entity_index);
+            return Ok(());
+        }
+        if delta != DeltaHeader::CREATE {
+            return Ok(());
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let server_time = ctx.tick() as f32 * self.tick_interval;
+        let spawn_time = entity
+            .get_value::<f32>(&CREATE_TIME_HASH)
+            .map(|create_time| self.game_time - (server_time - create_time).max(0.0));
+        if self.mid_boss.spawn(entity_index, spawn_time) {
+            self.send(
+                ctx,
+                DemoEventPayload::MidBossSpawned {
+                    entity_index,
+                    spawn_time,
+                    position: utils::get_entity_position(entity),
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Sends `mid_boss_killed` if a `BossKilled` message is about the mid boss, the securing team
+    /// is the team of the killer.
+    fn send_mid_boss_killed(
+        &mut self,
+        ctx: &Context,
+        msg: &CCitadelUserMsgBossKilled,
+    ) -> Result<(), DemoParseError> {
+        let Some(entity_index) = msg.entity_killed else {
+            return Ok(());
+        };
+        let Some(kill) = self.mid_boss.kill(entity_index, self.game_time) else {
+            return Ok(());
+        };
+        let killer = msg.entity_killer;
+        let pawn = killer.filter(|k| self.pawns.contains_key(k));
+        if !self.is_pawn_selected(pawn) {
+            return Ok(());
+        }
+        let controller = pawn
+            .and_then(|p| self.pawns.get(&p))
+            .and_then(|p| p.controller);
+        let (steam_id, hero_id) = self.player_ids(controller);
+        let securing_team = match pawn.and_then(|p| self.pawns.get(&p)) {
+            Some(pawn) => pawn.team,
+            None => killer
+                .and_then(|k| ctx.entities()?.get(&k))
+                .and_then(|k| k.get_value(&TEAM_HASH)),
+        };
+        debug!("Mid boss killed by {killer:?} of team {securing_team:?}");
+        self.send(
+            ctx,
+            DemoEventPayload::MidBossKilled {
+                entity_index,
+                securing_team,
+                killer,
+                pawn,
+                controller,
+                steam_id,
+                hero_id,
+                spawn_time: kill.spawn_time,
+                alive_duration: kill.alive_duration,
+                respawn_time: kill.respawn_time,
+            },
+        )
+    }
+
+    /// Resolves the entity at `entity_index` to its type, team and player.
+    fn damage_participant(&self, ctx: &Context, entity_index: Option<i32>) -> DamageParticipant {
+        let Some(index) = entity_index else {
+            return DamageParticipant::default();
+        };
+        let entity = ctx.entities().and_then(|e| e.get(&index));
+        let pawn = self.pawns.get(&index);
+        let controller = pawn.and_then(|p| p.controller);
+        let (steam_id, hero_id) = self.player_ids(controller);
+        DamageParticipant {
+            entity_index,
+            entity_type: entity.and_then(EntityType::from_opt),
+            team: pawn
+                .and_then(|p| p.team)
+                .or_else(|| entity.and_then(|e| e.get_value(&TEAM_HASH))),
+            controller,
+            steam_id,
+            hero_id,
+        }
+    }
+
+    /// Sends a `damage` event for a hit.
+    fn send_damage(
+        &self,
+        ctx: &Context,
+        msg: &CCitadelUserMessageDamage,
+    ) -> Result<(), DemoParseError> {
+        if !self.is_pawn_selected(msg.entindex_attacker)
+            && !self.is_pawn_selected(msg.entindex_victim)
+        {
+            return Ok(());
+        }
+        let flags = msg.flags.unwrap_or_default();
+        self.send(
+            ctx,
+            DemoEventPayload::Damage {
+                attacker: self.damage_participant(ctx, msg.entindex_attacker),
+                victim: self.damage_participant(ctx, msg.entindex_victim),
+                damage: msg.damage,
+                pre_damage: msg.pre_damage,
+                ability: msg.entindex_ability,
+                ability_id: msg.ability_id,
+                victim_health: msg.victim_health_new,
+                victim_max_health: msg.victim_health_max,
+                crit: flags & CRIT_DAMAGE_FLAG != 0,
+                flags,
+            },
+        )
+    }
+
+    /// Sends a `map_ping` event, the controller of the pinging player is the entity after their
+    /// player slot.
+    fn send_map_ping(
+        &self,
+        ctx: &Context,
+        msg: &CCitadelUserMsgMapPing,
+    ) -> Result<(), DemoParseError> {
+        let controller = msg.sender_player_slot.map(|slot| slot + 1);
+        let player = controller.and_then(|c| self.players.get(&c));
+        // Pings are only shown to the pinging team
+        if !self
+            .subscriptions
+            .chat_filter
+            .allows(Some(false), player.and_then(|p| p.team))
+            || !self.is_player_selected(controller)
+        {
+            return Ok(());
+        }
+        let position = msg.ping_location.as_ref().map(|location| {
+            [
+                location.x.unwrap_or_default(),
+                location.y.unwrap_or_default(),
+                location.z.unwrap_or_default(),
+            ]
+        });
+        self.send(
+            ctx,
+            DemoEventPayload::MapPing {
+                controller,
+                steam_id: player.and_then(|p| p.steam_id),
+                steam_name: player.and_then(|p| p.steam_name.clone()),
+                hero_id: player.and_then(|p| p.hero_id),
+                team: player.and_then(|p| p.team),
+                ping_type: msg.ping_message_id,
+                ping_event_type: msg.event_type,
+                entity_index: msg.entity_index,
+                minimap: msg.is_minimap_ping,
+                position,
+            },
+        )
+    }
+
+    /// Sends `entity_entered_region` and `entity_left_region` events, returns whether the update
+    /// of the entity should be sent.
+    fn update_region(
+        &mut self,
+        ctx: &Context,
+        delta: DeltaHeader,
+        entity_type: EntityType,
+        entity: &Entity,
+    ) -> Result<bool, DemoParseError> {
+        let Some(region) = self.region.as_mut() else {
+            return Ok(true);
+        };
+        let entity_index = entity.index();
+        let Some(position) = utils::get_entity_position(entity) else {
+            return Ok(true);
+        };
+        if delta == DeltaHeader::DELETE {
+            return Ok(region.remove(entity_index));
+        }
+        let change = region.update(entity_index, position);
+        let inside = region.is_inside(entity_index);
+        match change {
+            Some(RegionChange::Entered) => self.send(
+                ctx,
+                DemoEventPayload::EntityEnteredRegion {
+                    entity_index,
+                    entity_type,
+                    position,
+                },
+            )?,
+            Some(RegionChange::Left) => self.send(
+                ctx,
+                DemoEventPayload::EntityLeftRegion {
+                    entity_index,
+                    entity_type,
+                    position,
+                },
+            )?,
+            None => {}
+        }
+        Ok(inside)
+    }
+
+    /// Sends urn pickups and drops as well as `modifier_added` and `modifier_removed` events for
+    /// changes to the modifier table.
+    fn send_modifier_changes(&mut self, ctx: &Context) -> Result<(), DemoParseError> {
+        for change in self.modifiers.update(ctx) {
+            let (ModifierChange::Added(modifier) | ModifierChange::Removed(modifier)) = &change;
+            if is_urn_carrier_modifier(modifier.modifier_id)
+                && let Some(pawn) = modifier.target
+            {
+                let urn_change = match change {
+                    ModifierChange::Added(_) => self.urn.carrier_added(pawn),
+                    ModifierChange::Removed(_) => self.urn.carrier_removed(pawn),
+                };
+                self.send_urn_change(ctx, urn_change)?;
+            }
+            if !self.subscriptions.modifiers || !self.is_pawn_selected(modifier.target) {
+                continue;
+            }
+            let (controller, steam_id) = modifier
+                .target
+                .map(|target| utils::get_pawn_player(ctx, target))
+                .unwrap_or_default();
+            let event = match change {
+                ModifierChange::Added(modifier) => DemoEventPayload::ModifierAdded {
+                    controller,
+                    steam_id,
+                    modifier,
+                },
+                ModifierChange::Removed(modifier) => DemoEventPayload::ModifierRemoved {
+                    controller,
+                    steam_id,
+                    modifier,
+                },
+            };
+            self.send(ctx, event)?;
+        }
+        Ok(())
+    }
+
+    fn send_life_state_changes(&mut self, ctx: &Context) -> Result<(), DemoParseError> {
+        #[allow(clippy::cast_precision_loss)]
+        let server_time = ctx.tick() as f32 * self.tick_interval;
+        for change in core::mem::take(&mut self.life_state_changes) {
+            if !self.is_pawn_selected(Some(change.pawn)) {
+                continue;
+            }
+            let (steam_id, hero_id) = self.player_ids(change.controller);
+            let event = if change.alive {
+                DemoEventPayload::PlayerRespawned {
+                    pawn: change.pawn,
+                    controller: change.controller,
+                    steam_id,
+                    hero_id,
+                }
+            } else {
+                let respawn_time = change
+                    .controller
+                    .and_then(|c| self.players.get(&c))
+                    .and_then(|p| p.respawn_time);
+                DemoEventPayload::PlayerDied {
+                    pawn: change.pawn,
+                    controller: change.controller,
+                    steam_id,
+                    hero_id,
+                    respawn_time,
+                    respawn_in: respawn_time.map(|t| (t - server_time).max(0.0)),
+                }
+            };
+            self.send(ctx, event)?;
+        }
+        Ok(())
+    }
+
+    /// Sends `match_ended` without a winner if the broadcast ran out before the match ended.
+    pub(crate) fn finish(&mut self) -> Result<(), DemoParseError> {
+        self.send_match_ended(self.tick, None)
+    }
+
+    /// Sends the `match_ended` event with the final scoreboard, at most once per stream.
+    fn send_match_ended(
+        &mut self,
+        tick: i32,
+        winning_team: Option<u8>,
+    ) -> Result<(), DemoParseError> {
+        if self.match_ended {
+            return Ok(());
+        }
+        self.match_ended = true;
+        let mut players: Vec<_> = self.players.values().cloned().collect();
+        players.sort_by_key(|p| (p.team, p.player_slot));
+        self.send_at(
+            tick,
+            DemoEventPayload::MatchEnded {
+                winning_team,
+                duration: self.game_time,
+                players,
+            },
+        )
+    }
+
+    fn send_user_message(
+        &self,
+        ctx: &Context,
+        id: CitadelUserMessageIds,
+        message: serde_json::Value,
+    ) -> Result<(), DemoParseError> {
+        self.send(
+            ctx,
+            DemoEventPayload::UserMessage {
+                message_type: id.as_str_name(),
+                message_id: id as u32,
+                message,
+            },
+        )
+    }
+
+    fn send(&self, ctx: &Context, event: DemoEventPayload) -> Result<(), DemoParseError> {
+        self.send_at(ctx.tick(), event)
+    }
+
+    fn send_at(&self, tick: i32, event: DemoEventPayload) -> Result<(), DemoParseError> {
+        let demo_event = DemoEvent {
+            tick,
+            game_time: self.game_time,
+            event,
+        };
+        let event_type = demo_event.event.to_string();
+        let data = match &self.subscriptions.minimap {
+            Some(minimap) => {
+                let mut value = serde_json::to_value(&demo_event)?;
+                minimap.annotate(&mut value);
+                serde_json::to_string(&value)?
+            }
+            None => serde_json::to_string(&demo_event)?,
+        };
+        telemetry::record_sse_event(&event_type, data.len());
+        self.sender
+            .send(Event::default().event(event_type).data(data))?;
+        Ok(())
     }
 }
 
@@ -59,41 +822,83 @@ impl Visitor for SendingVisitor {
             return Ok(());
         };
 
+        if matches!(
+            entity_type,
+            EntityType::Ability | EntityType::AbilityMeleeParry
+        ) {
+            self.update_ability(ctx, delta_header, entity)?;
+        }
+
         if entity_type == EntityType::GameRulesProxy
             && let Some(rules) =
                 GameRulesProxyEvent::from_entity_update(ctx, delta_header.into(), entity)
         {
             debug!("Updating game rules");
+            self.update_pause_state(ctx, &rules)?;
+            if let Some(winning_team) = rules.winner() {
+                debug!("Team {winning_team} won, match ended");
+                self.send_match_ended(ctx.tick(), Some(winning_team))?;
+            }
             self.rules = rules;
         }
 
-        if self
-            .subscribed_entities
-            .as_ref()
-            .is_some_and(|e| !e.contains(&entity_type))
+        if entity_type == EntityType::PlayerController
+            && let Some(controller) =
+                PlayerControllerEvent::from_entity_update(ctx, delta_header.into(), entity)
         {
+            self.players.insert(entity.index(), controller);
+        }
+
+        if entity_type == EntityType::PlayerPawn {
+            self.track_pawn(delta_header, entity);
+            self.update_life_state(entity);
+            self.update_level(ctx, entity)?;
+        }
+
+        if entity_type == EntityType::MidBoss {
+            self.update_mid_boss(ctx, delta_header, entity)?;
+        }
+
+        if entity_type == EntityType::PunchablePowerup {
+            self.update_urn(ctx, delta_header, entity)?;
+        }
+
+        let subscribed = match &self.subscriptions.entities {
+            Some(entities) => entities.contains(&entity_type),
+            None => entity_type.is_default(),
+        };
+        if !subscribed {
+            return Ok(());
+        }
+
+        if !self.is_entity_selected(entity_type, entity) {
+            return Ok(());
+        }
+
+        if !self.update_region(ctx, delta_header, entity_type, entity)? {
             return Ok(());
         }
 
-        let Some(entity_update) =
+        let Some(mut entity_update) =
             EntityUpdateEvents::from_update(ctx, delta_header.into(), entity_type, entity)
         else {
             return Ok(());
         };
+        if self.subscriptions.modifiers
+            && let EntityUpdateEvents::PlayerPawn(pawn) = &mut entity_update
+        {
+            pawn.modifiers = Some(self.modifiers.of_target(entity.index()));
+        }
 
-        let demo_event = DemoEvent {
-            tick: ctx.tick(),
-            game_time: self.game_time,
-            event: DemoEventPayload::EntityUpdate {
+        self.send(
+            ctx,
+            DemoEventPayload::EntityUpdate {
                 delta: delta_header.into(),
                 entity_index: entity.index(),
                 entity_type,
                 entity_update,
             },
-        };
-        let sse_event = demo_event.try_into()?;
-        self.sender.send(sse_event)?;
-        Ok(())
+        )
     }
 
     async fn on_cmd(
@@ -106,6 +911,10 @@ impl Visitor for SendingVisitor {
             debug!("Updating tick interval");
             self.tick_interval = ctx.tick_interval();
         }
+        if cmd_header.cmd == EDemoCommands::DemStop {
+            debug!("Demo stopped, match ended");
+            self.send_match_ended(ctx.tick(), self.rules.winner())?;
+        }
         Ok(())
     }
 
@@ -115,43 +924,107 @@ impl Visitor for SendingVisitor {
         packet_type: u32,
         data: &[u8],
     ) -> Result<(), Self::Error> {
-        if self.subscribed_chat_messages
+        self.modifiers.on_packet(packet_type, data);
+
+        if self.subscriptions.chat_messages
             && packet_type == CitadelUserMessageIds::KEUserMsgChatMsg as u32
             && let Ok(msg) = CCitadelUserMsgChatMsg::decode(data)
             && let Some(tables) = ctx.string_tables()
             && let Some(table) = tables.find_table("userinfo")
             && let Some(player_slot) = msg.player_slot
+            && let team = self.players.get(&(player_slot + 1)).and_then(|p| p.team)
+            && self.subscriptions.chat_filter.allows(msg.all_chat, team)
+            && self.is_player_selected(Some(player_slot + 1))
         {
             let user_info = table.get_item(&player_slot);
             let user_data = user_info.and_then(StringTableItem::get_user_data);
             let user_info = user_data.and_then(|d| CMsgPlayerInfo::decode(d.as_ref()).ok());
-            let demo_event = DemoEvent {
-                tick: ctx.tick(),
-                game_time: self.game_time,
-                event: DemoEventPayload::ChatMessage {
+            self.send(
+                ctx,
+                DemoEventPayload::ChatMessage {
                     steam_name: user_info.as_ref().and_then(|u| u.name.clone()),
                     steam_id: user_info
                         .and_then(|u| u.steamid)
                         .and_then(|s| steamid64_to_steamid3(s).ok()),
-                    text: msg.text,
+                    text: msg
+                        .text
+                        .map(|text| self.subscriptions.chat_filter.mask(&text)),
+                    team,
                     all_chat: msg.all_chat,
                     lane_color: msg.lane_color,
                 },
-            };
-            let sse_event = demo_event.try_into()?;
-            self.sender.send(sse_event)?;
+            )?;
+        }
+
+        if self.subscriptions.map_pings
+            && packet_type == CitadelUserMessageIds::KEUserMsgMapPing as u32
+            && let Ok(msg) = CCitadelUserMsgMapPing::decode(data)
+        {
+            self.send_map_ping(ctx, &msg)?;
+        }
+
+        if packet_type == CitadelUserMessageIds::KEUserMsgReturnIdol as u32 {
+            let change = self.urn.delivered();
+            self.send_urn_change(ctx, change)?;
+        }
+
+        // Damage messages are very frequent, only decode them if needed and only once for both
+        // the damage events and the user message
+        let damage_id = CitadelUserMessageIds::KEUserMsgDamage;
+        let user_message = self.subscriptions.user_messages.contains(&packet_type);
+        if packet_type == damage_id as u32
+            && (self.subscriptions.damage || user_message)
+            && let Ok(msg) = CCitadelUserMessageDamage::decode(data)
+        {
+            if self.subscriptions.damage {
+                self.send_damage(ctx, &msg)?;
+            }
+            if user_message {
+                self.send_user_message(ctx, damage_id, serde_json::to_value(&msg)?)?;
+            }
+        } else if user_message
+            && let Some(id) = i32::try_from(packet_type)
+                .ok()
+                .and_then(|id| CitadelUserMessageIds::try_from(id).ok())
+            && let Some(message) = user_messages::decode(id, data)
+        {
+            self.send_user_message(ctx, id, message)?;
+        }
+
+        if packet_type == CitadelUserMessageIds::KEUserMsgBossKilled as u32
+            && let Ok(msg) = CCitadelUserMsgBossKilled::decode(data)
+        {
+            self.send_mid_boss_killed(ctx, &msg)?;
+        }
+
+        if packet_type == CitadelUserMessageIds::KEUserMsgRejuvStatus as u32
+            && let Ok(msg) = CCitadelUserMsgRejuvStatus::decode(data)
+            && msg.event_type == Some(REJUV_PICKED_UP)
+            && self.is_pawn_selected(msg.player_pawn.map(ehandle_to_index))
+        {
+            let pawn = msg.player_pawn.map(ehandle_to_index);
+            let controller = pawn
+                .and_then(|p| self.pawns.get(&p))
+                .and_then(|p| p.controller);
+            let (steam_id, hero_id) = self.player_ids(controller);
+            self.send(
+                ctx,
+                DemoEventPayload::RejuvPickedUp {
+                    pawn,
+                    controller,
+                    steam_id,
+                    hero_id,
+                    team: msg.user_team,
+                },
+            )?;
         }
 
         if packet_type == CitadelUserMessageIds::KEUserMsgHeroKilled as u32
             && let Ok(msg) = CCitadelUserMsgHeroKilled::decode(data)
+            && (self.is_pawn_selected(msg.entindex_victim)
+                || self.is_pawn_selected(msg.entindex_attacker))
         {
-            let demo_event = DemoEvent {
-                tick: ctx.tick(),
-                game_time: self.game_time,
-                event: DemoEventPayload::HeroKilled(msg),
-            };
-            let sse_event = demo_event.try_into()?;
-            self.sender.send(sse_event)?;
+            self.send(ctx, DemoEventPayload::HeroKilled(msg))?;
         }
 
         Ok(())
@@ -160,17 +1033,55 @@ impl Visitor for SendingVisitor {
     async fn on_tick_end(&mut self, ctx: &Context) -> Result<(), Self::Error> {
         #[allow(clippy::cast_precision_loss)]
         {
-            let ticks = ctx.tick() - self.rules.total_paused_ticks.unwrap_or_default();
+            // Freezes game time during pauses
+            let ticks = ctx.tick() - self.paused_ticks(ctx.tick());
             let total_time = ticks as f32 * self.tick_interval;
             self.game_time = total_time - self.rules.game_start_time.unwrap_or_default();
         }
+        self.tick = ctx.tick();
+        self.progress.update(ctx.tick(), self.game_time);
+        self.send_life_state_changes(ctx)?;
+        self.send_modifier_changes(ctx)?;
 
-        let demo_event = DemoEvent {
-            tick: ctx.tick(),
-            game_time: self.game_time,
-            event: DemoEventPayload::TickEnd,
-        };
-        self.sender.send(demo_event.try_into()?)?;
-        Ok(())
+        self.send(ctx, DemoEventPayload::TickEnd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(steam_ids: &[u32], hero_ids: &[u32]) -> PlayerFilter {
+        PlayerFilter {
+            steam_ids: steam_ids.iter().copied().collect(),
+            hero_ids: hero_ids.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn steam_ids_and_hero_ids_are_combined() {
+        let filter = filter(&[100], &[6]);
+        assert!(filter.matches(&PlayerControllerEvent::for_player(1, 100, 1)));
+        assert!(filter.matches(&PlayerControllerEvent::for_player(2, 200, 6)));
+        assert!(filter.matches(&PlayerControllerEvent::for_player(3, 100, 6)));
+        assert!(!filter.matches(&PlayerControllerEvent::for_player(4, 200, 1)));
+    }
+
+    #[test]
+    fn players_without_ids_are_not_selected() {
+        let filter = filter(&[100], &[6]);
+        assert!(!filter.matches(&PlayerControllerEvent::default()));
+    }
+
+    #[test]
+    fn pawns_are_resolved_through_their_controller() {
+        let filter = filter(&[], &[6]);
+        let players = [
+            PlayerControllerEvent::for_player(10, 100, 6),
+            PlayerControllerEvent::for_player(11, 200, 1),
+        ];
+        assert!(filter.matches_pawn(&players, 10));
+        assert!(!filter.matches_pawn(&players, 11));
+        assert!(!filter.matches_pawn(&players, 12));
     }
 }
@@ -1,12 +1,15 @@
 use axum::body::Body;
-use axum::http::Response;
+use axum::http::{Response, header};
 use axum::response::IntoResponse;
 use reqwest::StatusCode;
 use serde_json::json;
+use strum::IntoStaticStr;
 use thiserror::Error;
 use tracing::error;
 
+use crate::demo_parser::error::DemoParseError;
 use crate::state::AppStateError;
+use crate::telemetry;
 
 pub(super) type APIResult<T> = Result<T, APIError>;
 
@@ -20,6 +23,68 @@ pub enum StartupError {
     AppState(#[from] AppStateError),
 }
 
+/// Machine-readable codes for failures caused by the upstream Deadlock API or broadcast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub(super) enum UpstreamErrorCode {
+    /// The match does not exist or is not live.
+    MatchNotFound,
+    /// The Deadlock API rate limited our requests.
+    UpstreamRateLimited,
+    /// The Deadlock API rejected our API key.
+    UpstreamUnauthorized,
+    /// The Deadlock API or broadcast did not respond in time.
+    UpstreamTimeout,
+    /// The Deadlock API or broadcast failed otherwise.
+    UpstreamError,
+    /// The match is live, but its demo broadcast did not become available.
+    DemoNotAvailable,
+}
+
+impl UpstreamErrorCode {
+    fn status(self) -> StatusCode {
+        match self {
+            Self::MatchNotFound => StatusCode::NOT_FOUND,
+            Self::UpstreamRateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::UpstreamUnauthorized | Self::UpstreamError => StatusCode::BAD_GATEWAY,
+            Self::UpstreamTimeout | Self::DemoNotAvailable => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    /// Classifies a failed upstream request.
+    pub(super) fn from_request_error(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return Self::UpstreamTimeout;
+        }
+        match error.status() {
+            Some(StatusCode::NOT_FOUND) => Self::MatchNotFound,
+            Some(StatusCode::TOO_MANY_REQUESTS) => Self::UpstreamRateLimited,
+            Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => Self::UpstreamUnauthorized,
+            Some(StatusCode::GATEWAY_TIMEOUT) => Self::UpstreamTimeout,
+            _ => Self::UpstreamError,
+        }
+    }
+
+    /// Whether retrying the request may succeed.
+    pub(super) fn is_transient(self) -> bool {
+        matches!(
+            self,
+            Self::UpstreamRateLimited | Self::UpstreamTimeout | Self::UpstreamError
+        )
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            Self::MatchNotFound => "Match not found or not live.",
+            Self::UpstreamRateLimited => "Rate limited by the Deadlock API, try again later.",
+            Self::UpstreamUnauthorized => "The Deadlock API rejected the server's API key.",
+            Self::UpstreamTimeout => "The Deadlock API did not respond in time.",
+            Self::UpstreamError => "Request to the Deadlock API failed.",
+            Self::DemoNotAvailable => "The demo of the match is not available yet.",
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Error)]
 pub(super) enum APIError {
@@ -34,6 +99,11 @@ pub(super) enum APIError {
     },
     #[error("Internal server error: {message}")]
     InternalError { message: String },
+    #[error("{message}")]
+    Upstream {
+        code: UpstreamErrorCode,
+        message: String,
+    },
     #[error("Protobuf Error: {0}")]
     Protobuf(#[from] prost::DecodeError),
     #[error("Request Error: {0}")]
@@ -46,6 +116,50 @@ impl APIError {
             message: message.into(),
         }
     }
+
+    pub(super) fn upstream(code: UpstreamErrorCode) -> Self {
+        Self::Upstream {
+            code,
+            message: code.message().to_owned(),
+        }
+    }
+}
+
+impl From<DemoParseError> for APIError {
+    fn from(error: DemoParseError) -> Self {
+        match error {
+            DemoParseError::Broadcast(e) => Self::Upstream {
+                code: UpstreamErrorCode::UpstreamError,
+                message: format!("Failed to stream the demo broadcast: {e}"),
+            },
+            e => Self::internal(e.to_string()),
+        }
+    }
+}
+
+/// Snake case name of a status code, used as the error code of errors without a more specific one.
+fn status_code_name(status: StatusCode) -> String {
+    status
+        .canonical_reason()
+        .unwrap_or("error")
+        .to_lowercase()
+        .replace([' ', '-'], "_")
+}
+
+fn json_error_response(status: StatusCode, code: &str, error: serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(
+            serde_json::to_string(&json!({
+                "status": status.as_u16(),
+                "code": code,
+                "error": error,
+            }))
+            .unwrap_or_else(|_| "Internal server error".to_owned())
+            .into(),
+        )
+        .unwrap_or_else(|_| "Internal server error".to_owned().into_response())
 }
 
 impl IntoResponse for APIError {
@@ -56,43 +170,27 @@ impl IntoResponse for APIError {
                 .status(status)
                 .body(Body::empty())
                 .unwrap_or_else(|_| "Internal server error".to_owned().into_response()),
-            Self::StatusMsg { status, message } => Response::builder()
-                .status(status)
-                .body(
-                    serde_json::to_string(&json!({
-                        "status": status.as_u16(),
-                        "error": message,
-                    }))
-                    .unwrap_or_else(|_| "Internal server error".to_owned())
-                    .into(),
-                )
-                .unwrap_or_else(|_| "Internal server error".to_owned().into_response()),
-            Self::StatusMsgJson { status, message } => Response::builder()
-                .status(status)
-                .body(
-                    serde_json::to_string(&json!({
-                        "status": status.as_u16(),
-                        "error": message,
-                    }))
-                    .unwrap_or_else(|_| "Internal server error".to_owned())
-                    .into(),
-                )
-                .unwrap_or_else(|_| "Internal server error".to_owned().into_response()),
-            Self::InternalError { message } => Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(
-                    serde_json::to_string(&json!({
-                        "status": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-                        "error": format!("Internal server error: {message}"),
-                    }))
-                    .unwrap_or_else(|_| "Internal server error".to_owned())
-                    .into(),
-                )
-                .unwrap_or_else(|_| "Internal server error".to_owned().into_response()),
+            Self::StatusMsg { status, message } => {
+                json_error_response(status, &status_code_name(status), json!(message))
+            }
+            Self::StatusMsgJson { status, message } => {
+                json_error_response(status, &status_code_name(status), message)
+            }
+            Self::InternalError { message } => json_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &status_code_name(StatusCode::INTERNAL_SERVER_ERROR),
+                json!(format!("Internal server error: {message}")),
+            ),
+            Self::Upstream { code, message } => {
+                telemetry::record_upstream_error(code);
+                json_error_response(code.status(), code.into(), json!(message))
+            }
             Self::Protobuf(_) => {
                 Self::internal("Failed to parse protobuf message.").into_response()
             }
-            Self::Request(_) => Self::internal("Request failed.").into_response(),
+            Self::Request(e) => {
+                Self::upstream(UpstreamErrorCode::from_request_error(&e)).into_response()
+            }
         }
     }
 }
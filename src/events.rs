@@ -1,5 +1,6 @@
 use core::time::Duration;
 use std::string::ToString;
+use std::time::Instant;
 
 use async_stream::try_stream;
 use axum::extract::{Path, Query, State};
@@ -12,24 +13,98 @@ use haste::parser::Parser;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use strum::VariantArray;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
+use crate::auth::{ClientKey, Endpoint};
+use crate::demo_parser::chat::ChatFilter;
 use crate::demo_parser::entity_events::EntityType;
 use crate::demo_parser::error::DemoParseError;
-use crate::demo_parser::visitor::SendingVisitor;
-use crate::error::{APIError, APIResult};
-use crate::state::AppState;
+use crate::demo_parser::minimap::Coords;
+use crate::demo_parser::region::Region;
+use crate::demo_parser::user_messages::UserMessageId;
+use crate::demo_parser::visitor::{PlayerFilter, SendingVisitor, Subscriptions};
+use crate::error::APIResult;
+use crate::state::{AppState, Config};
+use crate::streams::StreamHandle;
+use crate::telemetry;
 use crate::utils;
 use crate::utils::comma_separated_deserialize_option;
 
+/// Consecutive failed fragment fetches after which the stream is ended.
+const MAX_CONSECUTIVE_FETCH_ERRORS: usize = 5;
+
 #[derive(Serialize, Deserialize)]
 pub(super) struct DemoEventsQuery {
     /// Subscribe to chat messages.
     #[serde(default)]
     subscribed_chat_messages: Option<bool>,
+    /// Only send all-chat messages, hiding team chat.
+    #[serde(default)]
+    chat_all_chat_only: Option<bool>,
+    /// Only send chat messages of players on this team.
+    #[serde(default)]
+    chat_team: Option<u8>,
+    /// Subscribe to map pings, filtered like team chat messages.
+    #[serde(default)]
+    subscribed_map_pings: Option<bool>,
     /// Comma separated list of entities to subscribe to.
     #[serde(default, deserialize_with = "comma_separated_deserialize_option")]
     subscribed_entities: Option<Vec<EntityType>>,
+    /// Subscribe to modifier changes and include active modifiers in player pawn updates.
+    #[serde(default)]
+    subscribed_modifiers: Option<bool>,
+    /// Subscribe to damage events of every hit.
+    #[serde(default)]
+    subscribed_damage: Option<bool>,
+    /// Comma separated list of user messages to subscribe to.
+    #[serde(default, deserialize_with = "comma_separated_deserialize_option")]
+    subscribed_user_messages: Option<Vec<UserMessageId>>,
+    /// Coordinate system of positions.
+    #[serde(default)]
+    coords: Option<Coords>,
+    /// Only stream updates of entities inside this box (`x1,y1,x2,y2`) or circle (`x,y,radius`).
+    #[serde(default)]
+    region: Option<Region>,
+    /// Comma separated list of steam ids of the players to stream player events of.
+    #[serde(default, deserialize_with = "comma_separated_deserialize_option")]
+    steam_ids: Option<Vec<u32>>,
+    /// Comma separated list of hero ids of the players to stream player events of.
+    #[serde(default, deserialize_with = "comma_separated_deserialize_option")]
+    hero_ids: Option<Vec<u32>>,
+}
+
+impl DemoEventsQuery {
+    fn into_subscriptions(self, config: &Config) -> Subscriptions {
+        Subscriptions {
+            chat_messages: self.subscribed_chat_messages.unwrap_or_default(),
+            chat_filter: ChatFilter::new(
+                self.chat_all_chat_only.unwrap_or_default() || config.chat_all_chat_only,
+                self.chat_team,
+                config.chat_masked_words.as_deref().unwrap_or_default(),
+            ),
+            map_pings: self.subscribed_map_pings.unwrap_or_default(),
+            entities: self
+                .subscribed_entities
+                .map(|entities| entities.into_iter().collect()),
+            modifiers: self.subscribed_modifiers.unwrap_or_default(),
+            damage: self.subscribed_damage.unwrap_or_default(),
+            user_messages: self
+                .subscribed_user_messages
+                .into_iter()
+                .flatten()
+                .map(|UserMessageId(id)| id as u32)
+                .collect(),
+            minimap: (self.coords == Some(Coords::Minimap)).then(|| config.minimap()),
+            region: self.region,
+            players: (self.steam_ids.is_some() || self.hero_ids.is_some()).then(|| PlayerFilter {
+                steam_ids: self.steam_ids.into_iter().flatten().collect(),
+                hero_ids: self.hero_ids.into_iter().flatten().collect(),
+            }),
+            mid_boss_respawn_delay: config.mid_boss_respawn_delay_secs,
+        }
+    }
 }
 
 fn all_sse_events() -> Vec<String> {
@@ -43,67 +118,179 @@ fn all_sse_events() -> Vec<String> {
             ]
         })
         .chain(
-            ["tick_end", "hero_killed", "end"]
-                .into_iter()
-                .map(ToString::to_string),
+            [
+                "tick_end",
+                "entity_entered_region",
+                "entity_left_region",
+                "hero_killed",
+                "map_ping",
+                "game_paused",
+                "game_resumed",
+                "player_died",
+                "player_respawned",
+                "player_level_up",
+                "ability_upgraded",
+                "modifier_added",
+                "modifier_removed",
+                "damage",
+                "user_message",
+                "urn_spawned",
+                "urn_picked_up",
+                "urn_dropped",
+                "urn_delivered",
+                "mid_boss_spawned",
+                "mid_boss_killed",
+                "rejuv_picked_up",
+                "match_ended",
+                "server_shutdown",
+                "error",
+                "end",
+            ]
+            .into_iter()
+            .map(ToString::to_string),
         )
         .collect()
 }
 
-fn send_info_event() -> Result<Event, axum::Error> {
-    Event::default().event("message").json_data(json!({
+fn send_info_event() -> Result<Event, serde_json::Error> {
+    let data = serde_json::to_string(&json!({
         "status": "connected",
         "message": "Connected to demo event stream.",
         "eventsource_disclaimer": "Server-Sent Events use various event names, so the onmessage event listener won't catch them because it only listens to the default 'message' event. I recommend using a library like sse.js.",
         "all_event_names": all_sse_events(),
-    }))
+    }))?;
+    telemetry::record_sse_event("message", data.len());
+    Ok(Event::default().event("message").data(data))
+}
+
+fn send_shutdown_event(reconnect_delay: Duration) -> Result<Event, serde_json::Error> {
+    let data = serde_json::to_string(&json!({
+        "reason": EndReason::ServerShutdown,
+        "reconnect_delay_ms": reconnect_delay.as_millis(),
+    }))?;
+    telemetry::record_sse_event("server_shutdown", data.len());
+    Ok(Event::default()
+        .event("server_shutdown")
+        .retry(reconnect_delay)
+        .data(data))
+}
+
+fn send_error_event(error: &DemoParseError) -> Result<Event, serde_json::Error> {
+    let data = serde_json::to_string(&json!({
+        "code": error.kind(),
+        "message": error.to_string(),
+    }))?;
+    telemetry::record_sse_event("error", data.len());
+    Ok(Event::default().event("error").data(data))
+}
+
+/// Why an event stream ended, sent with the `end` event.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum EndReason {
+    /// The broadcast ended with the match.
+    MatchOver,
+    /// The broadcast could not be fetched anymore.
+    UpstreamError,
+    /// The demo could not be parsed.
+    ParseError,
+    /// The server is shutting down.
+    ServerShutdown,
+}
+
+fn send_end_event(reason: EndReason) -> Result<Event, serde_json::Error> {
+    let data = serde_json::to_string(&json!({"reason": reason}))?;
+    telemetry::record_sse_event("end", data.len());
+    Ok(Event::default().event("end").data(data))
+}
+
+fn send_event(sender: &UnboundedSender<Event>, event: Result<Event, serde_json::Error>) {
+    match event {
+        Ok(event) => {
+            if let Err(e) = sender.send(event) {
+                warn!("Failed to send event: {e}");
+            }
+        }
+        Err(e) => error!("Failed to serialize event: {e}"),
+    }
 }
 
 async fn demo_event_stream(
+    stream_handle: StreamHandle,
     broadcast_url: impl Into<String>,
-    query: DemoEventsQuery,
+    subscriptions: Subscriptions,
+    shutdown: CancellationToken,
+    reconnect_delay: Duration,
 ) -> Result<impl Stream<Item = Result<Event, DemoParseError>>, DemoParseError> {
     let client = reqwest::Client::new();
     let demo_stream = BroadcastHttp::start_streaming(client, broadcast_url).await?;
     let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
-    let visitor = SendingVisitor::new(
-        sender.clone(),
-        query.subscribed_chat_messages.unwrap_or_default(),
-        query.subscribed_entities,
-    );
+    let visitor = SendingVisitor::new(sender.clone(), stream_handle.progress(), subscriptions);
     let mut parser = Parser::from_stream_with_visitor(demo_stream, visitor)?;
     tokio::spawn(async move {
-        loop {
+        let mut fetch_errors = 0;
+        let reason = loop {
             if sender.is_closed() {
                 warn!("Channel closed, ending demo stream");
-                break;
+                return;
             }
             let demo_stream = parser.demo_stream_mut();
             debug!("Waiting for next packet in demo stream");
-            match demo_stream.next_packet().await {
+            let fetch_start = Instant::now();
+            let packet = tokio::select! {
+                packet = demo_stream.next_packet() => packet,
+                () = shutdown.cancelled() => {
+                    debug!("Server shutting down, ending demo stream");
+                    send_event(&sender, send_shutdown_event(reconnect_delay));
+                    break EndReason::ServerShutdown;
+                }
+            };
+            telemetry::record_fragment_fetch(
+                fetch_start.elapsed().as_secs_f64(),
+                !matches!(packet, Some(Err(_))),
+            );
+            match packet {
                 Some(Ok(_)) => {
-                    if let Err(e) = parser.run_to_end().await {
-                        error!("Error while parsing demo stream: {e}");
-                        break;
+                    fetch_errors = 0;
+                    match parser.run_to_end().await {
+                        Ok(()) => {}
+                        Err(DemoParseError::Send(_)) => {
+                            debug!("Client disconnected, ending demo stream");
+                            return;
+                        }
+                        Err(e) => {
+                            error!("Error while parsing demo stream: {e}");
+                            telemetry::record_parse_error(&e);
+                            send_event(&sender, send_error_event(&e));
+                            break EndReason::ParseError;
+                        }
                     }
                 }
                 Some(Err(err)) => {
-                    error!("Error while parsing demo stream: {err}");
+                    error!("Error while fetching demo stream: {err}");
+                    fetch_errors += 1;
+                    if fetch_errors >= MAX_CONSECUTIVE_FETCH_ERRORS {
+                        send_event(&sender, send_error_event(&err.into()));
+                        break EndReason::UpstreamError;
+                    }
                 }
                 None => {
                     debug!("Demo stream ended");
-                    if let Err(e) = sender.send(Event::default().event("end").data("end")) {
-                        warn!("Failed to send end event: {e}");
+                    if let Err(e) = parser.visitor_mut().finish() {
+                        warn!("Failed to send final events: {e}");
                     }
-                    break;
+                    break EndReason::MatchOver;
                 }
             }
-        }
+        };
+        send_event(&sender, send_end_event(reason));
     });
     Ok(try_stream! {
+        let _stream_handle = stream_handle;
         info!("Starting to parse demo stream");
         yield send_info_event()?;
         while let Some(event) = receiver.recv().await {
+            telemetry::record_channel_backlog(receiver.len());
             yield event;
         }
     })
@@ -113,33 +300,27 @@ pub(super) async fn events(
     Path(match_id): Path<u64>,
     Query(body): Query<DemoEventsQuery>,
     State(state): State<AppState>,
+    client_key: ClientKey,
 ) -> APIResult<impl IntoResponse> {
-    info!("Spectating match {match_id}");
-    let response = tryhard::retry_fn(|| {
-        utils::spectate_match(
-            &state.http_client,
-            match_id,
-            state.config.deadlock_api_key.as_ref().map(AsRef::as_ref),
-        )
-    })
-    .retries(3)
-    .fixed_backoff(Duration::from_millis(200))
+    state.ensure_accepting_streams()?;
+    let permit = state.clients.acquire(&client_key, Endpoint::Events)?;
+    let broadcast_url = utils::spectate_live_demo(
+        &state.http_client,
+        &state.config.deadlock_api_base_url,
+        match_id,
+        state.config.deadlock_api_key.as_deref(),
+    )
     .await?;
 
-    // Wait for the demo to be available
-    tryhard::retry_fn(|| async {
-        utils::live_demo_exists(&state.http_client, &response.broadcast_url).await
-    })
-    .retries(60)
-    .fixed_backoff(Duration::from_millis(500))
-    .await
-    .map_err(|e| APIError::internal(format!("Failed to spectate match: {e}")))?;
-
-    info!("Demo available for match {match_id}");
-    let stream = demo_event_stream(response.broadcast_url, body)
-        .await
-        .map_err(|e| APIError::internal(e.to_string()))?
-        .inspect_err(|e| error!("Error in demo event stream: {e}"));
+    let stream = demo_event_stream(
+        state.streams.register(match_id, Endpoint::Events, permit),
+        broadcast_url,
+        body.into_subscriptions(&state.config),
+        state.shutdown.clone(),
+        Duration::from_secs(state.config.shutdown_reconnect_delay_secs),
+    )
+    .await?
+    .inspect_err(|e| error!("Error in demo event stream: {e}"));
 
     let headers = HeaderMap::from_iter([
         (
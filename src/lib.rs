@@ -11,27 +11,55 @@
 #![allow(clippy::unreadable_literal)]
 #![allow(clippy::missing_errors_doc)]
 
+mod auth;
+mod debug;
 mod demo;
 mod demo_parser;
 mod error;
 mod events;
 mod state;
+mod status;
+mod streams;
+mod telemetry;
 pub mod utils;
 
+pub use auth::{ClientApiKey, Endpoint};
 use axum::Router;
+use axum::http::HeaderValue;
 use axum::routing::get;
 pub use error::*;
-use tower_http::cors::CorsLayer;
+pub use state::{AppStateError, Config, ListenAddress};
+use tokio_util::sync::CancellationToken;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::normalize_path::{NormalizePath, NormalizePathLayer};
 use tower_layer::Layer;
-use tracing::debug;
+use tracing::warn;
 
 use crate::state::AppState;
 
-pub fn router() -> Result<NormalizePath<Router>, StartupError> {
-    debug!("Loading application state");
-    let state = AppState::from_env()?;
-    debug!("Application state loaded");
+fn cors_layer(allowed_origins: Option<&[String]>) -> CorsLayer {
+    let Some(allowed_origins) = allowed_origins.filter(|o| !o.iter().any(|o| o == "*")) else {
+        return CorsLayer::permissive();
+    };
+    let origins: Vec<HeaderValue> = allowed_origins
+        .iter()
+        .filter_map(|origin| {
+            HeaderValue::from_str(origin)
+                .inspect_err(|e| warn!("Ignoring invalid CORS origin {origin}: {e}"))
+                .ok()
+        })
+        .collect();
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods(Any)
+        .allow_headers(Any)
+}
+
+/// Builds the application router. Open streams are ended once `shutdown` is cancelled.
+#[must_use]
+pub fn router(config: Config, shutdown: CancellationToken) -> NormalizePath<Router> {
+    let cors = cors_layer(config.cors_allowed_origins.as_deref());
+    let state = AppState::new(config, shutdown);
 
     let router = Router::new()
         .route(
@@ -39,7 +67,12 @@ pub fn router() -> Result<NormalizePath<Router>, StartupError> {
             get(events::events),
         )
         .route("/v1/matches/{match_id}/live/demo", get(demo::demo))
-        .layer(CorsLayer::permissive())
+        .route("/debug/matches/{match_id}/schema", get(debug::schema))
+        .route("/v1/streams", get(status::streams))
+        .route("/health", get(status::health))
+        .route("/ready", get(status::ready))
+        .route("/metrics", get(telemetry::prometheus_metrics))
+        .layer(cors)
         .with_state(state);
-    Ok(NormalizePathLayer::trim_trailing_slash().layer(router))
+    NormalizePathLayer::trim_trailing_slash().layer(router)
 }
@@ -3,18 +3,21 @@
 #![deny(unreachable_pub)]
 #![deny(clippy::pedantic)]
 
-use std::net::{Ipv4Addr, SocketAddr};
+use core::net::SocketAddr;
+use core::time::Duration;
+use std::path::Path;
 
-use axum::ServiceExt;
 use axum::extract::Request;
-use deadlock_live_events::{StartupError, router};
-use tracing::info;
+use axum::{Router, ServiceExt};
+use axum_server::tls_rustls::RustlsConfig;
+use deadlock_live_events::{Config, ListenAddress, StartupError, router};
+use tokio_util::sync::CancellationToken;
+use tower_http::normalize_path::NormalizePath;
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
-const PORT: u16 = 3000;
-
 fn init_tracing() {
     let env_filter = EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new(
         "debug,hyper_util=warn,tower_http=info,reqwest=warn,rustls=warn,sqlx=warn,h2=warn",
@@ -27,15 +30,141 @@ fn init_tracing() {
         .init();
 }
 
+/// Cancels `shutdown` on SIGINT or SIGTERM.
+async fn shutdown_signal(shutdown: CancellationToken) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for SIGINT: {e}");
+            core::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {e}");
+                core::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = core::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
+    info!("Shutdown signal received, closing open streams");
+    shutdown.cancel();
+}
+
+async fn serve_tcp(
+    address: SocketAddr,
+    router: NormalizePath<Router>,
+    shutdown: CancellationToken,
+) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(&address).await?;
+    info!("Listening on http://{address}");
+    axum::serve(listener, ServiceExt::<Request>::into_make_service(router))
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await
+}
+
+async fn serve_tls(
+    address: SocketAddr,
+    tls: RustlsConfig,
+    router: NormalizePath<Router>,
+    shutdown: CancellationToken,
+) -> std::io::Result<()> {
+    let handle = axum_server::Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown.cancelled().await;
+        shutdown_handle.graceful_shutdown(None);
+    });
+    info!("Listening on https://{address}");
+    axum_server::bind_rustls(address, tls)
+        .handle(handle)
+        .serve(ServiceExt::<Request>::into_make_service(router))
+        .await
+}
+
+#[cfg(unix)]
+async fn serve_unix(
+    path: &Path,
+    router: NormalizePath<Router>,
+    shutdown: CancellationToken,
+) -> std::io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // Remove the socket of a previous run, but never anything else
+    if std::fs::metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    info!("Listening on unix:{}", path.display());
+    axum::serve(listener, ServiceExt::<Request>::into_make_service(router))
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::unused_async)]
+async fn serve_unix(
+    _path: &Path,
+    _router: NormalizePath<Router>,
+    _shutdown: CancellationToken,
+) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Unix sockets are not supported on this platform",
+    ))
+}
+
 #[tokio::main]
 async fn main() -> Result<(), StartupError> {
     init_tracing();
 
-    let router = router()?;
-    let address = SocketAddr::from((Ipv4Addr::UNSPECIFIED, PORT));
-    let listener = tokio::net::TcpListener::bind(&address).await?;
+    debug!("Loading config");
+    let config = Config::from_env()?;
+    let listen_address = config.listen_address()?;
+    let tls = match config.tls_paths()? {
+        Some((cert, key)) => Some(RustlsConfig::from_pem_file(cert, key).await?),
+        None => None,
+    };
+    debug!("Config loaded");
 
-    info!("Listening on http://{address}");
-    axum::serve(listener, ServiceExt::<Request>::into_make_service(router)).await?;
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    let shutdown = CancellationToken::new();
+    tokio::spawn(shutdown_signal(shutdown.clone()));
+
+    let router = router(config, shutdown.clone());
+    let server = async {
+        match (listen_address, tls) {
+            (ListenAddress::Tcp(address), None) => {
+                serve_tcp(address, router, shutdown.clone()).await
+            }
+            (ListenAddress::Tcp(address), Some(tls)) => {
+                serve_tls(address, tls, router, shutdown.clone()).await
+            }
+            (ListenAddress::Unix(path), None) => serve_unix(&path, router, shutdown.clone()).await,
+            (ListenAddress::Unix(_), Some(_)) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "TLS is not supported on Unix sockets",
+            )),
+        }
+    };
+    let drain_timeout = async {
+        shutdown.cancelled().await;
+        tokio::time::sleep(shutdown_timeout).await;
+    };
+    tokio::select! {
+        result = server => result?,
+        () = drain_timeout => warn!("Timed out waiting for open connections to close"),
+    }
     Ok(())
 }
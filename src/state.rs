@@ -1,32 +1,304 @@
-use serde::Deserialize;
+use core::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+use metrics_exporter_prometheus::PrometheusHandle;
+use reqwest::StatusCode;
+use serde::{Deserialize, Deserializer};
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+use crate::auth::{ClientApiKey, ClientKeys, client_api_keys_deserialize};
+use crate::demo_parser::minimap::{MapZones, Minimap};
+use crate::error::{APIError, APIResult};
+use crate::streams::StreamRegistry;
+use crate::telemetry;
+use crate::utils::comma_separated_deserialize_option;
 
 #[derive(Debug, Error)]
 pub enum AppStateError {
     #[error("Parsing error: {0}")]
     ParsingConfig(#[from] serde_env::Error),
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+}
+
+fn default_listen_address() -> String {
+    "0.0.0.0".to_owned()
+}
+
+fn default_port() -> u16 {
+    3000
+}
+
+fn default_deadlock_api_base_url() -> String {
+    "https://api.deadlock-api.com".to_owned()
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    10
+}
+
+fn default_shutdown_reconnect_delay_secs() -> u64 {
+    5
+}
+
+fn default_mid_boss_respawn_delay_secs() -> f32 {
+    420.0
+}
+
+fn default_map_lane_centers() -> [f32; 3] {
+    [0.2, 0.5, 0.8]
+}
+
+fn default_map_lane_half_width() -> f32 {
+    0.07
+}
+
+fn default_map_base_distance() -> f32 {
+    0.38
+}
+
+fn default_map_bottom_team() -> u8 {
+    2
+}
+
+fn default_map_min() -> f32 {
+    -10752.0
+}
+
+fn default_map_max() -> f32 {
+    10752.0
+}
+
+/// Splits a comma separated list of origins, unlike `comma_separated_deserialize_option` this
+/// keeps the brackets of IPv6 origins like `http://[::1]:3000`.
+fn origins_deserialize<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let origins: Vec<String> = Option::<String>::deserialize(deserializer)?
+        .iter()
+        .flat_map(|origins| origins.split(','))
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(ToOwned::to_owned)
+        .collect();
+    Ok((!origins.is_empty()).then_some(origins))
+}
+
+fn map_lane_centers_deserialize<'de, D>(deserializer: D) -> Result<[f32; 3], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match comma_separated_deserialize_option::<D, f32>(deserializer)? {
+        Some(centers) => centers
+            .try_into()
+            .map_err(|_| serde::de::Error::custom("map_lane_centers needs three values")),
+        None => Ok(default_map_lane_centers()),
+    }
+}
+
+/// Where the server accepts connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddress {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
 }
 
 #[derive(Deserialize, Debug, Clone)]
-pub(crate) struct Config {
+pub struct Config {
+    /// IPv4 or IPv6 address to listen on, or `unix:<path>` to listen on a Unix socket.
+    #[serde(default = "default_listen_address")]
+    pub listen_address: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Comma separated list of origins allowed to make cross-origin requests, all if unset.
+    #[serde(default, deserialize_with = "origins_deserialize")]
+    pub cors_allowed_origins: Option<Vec<String>>,
+    /// PEM encoded certificate chain, serves HTTPS together with `tls_key_path`.
+    #[serde(default)]
+    pub tls_cert_path: Option<PathBuf>,
+    /// PEM encoded private key, serves HTTPS together with `tls_cert_path`.
     #[serde(default)]
-    pub(crate) deadlock_api_key: Option<String>,
+    pub tls_key_path: Option<PathBuf>,
+    #[serde(default)]
+    pub deadlock_api_key: Option<String>,
+    /// Base URL of the Deadlock API used to resolve the broadcast url of a match.
+    #[serde(default = "default_deadlock_api_base_url")]
+    pub deadlock_api_base_url: String,
+    /// How long to wait for open connections to close on shutdown.
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// Reconnect delay suggested to clients in the `server_shutdown` event.
+    #[serde(default = "default_shutdown_reconnect_delay_secs")]
+    pub shutdown_reconnect_delay_secs: u64,
+    /// Only send all-chat messages to every client, regardless of `chat_all_chat_only`.
+    #[serde(default)]
+    pub chat_all_chat_only: bool,
+    /// Comma separated list of words masked in chat messages, only letters and digits.
+    #[serde(default, deserialize_with = "comma_separated_deserialize_option")]
+    pub chat_masked_words: Option<Vec<String>>,
+    /// Seconds between the death of the mid boss and its respawn, for `mid_boss_killed` events.
+    #[serde(default = "default_mid_boss_respawn_delay_secs")]
+    pub mid_boss_respawn_delay_secs: f32,
+    /// World coordinates of the edges of the minimap, used by `coords=minimap`.
+    #[serde(default = "default_map_min")]
+    pub map_min_x: f32,
+    #[serde(default = "default_map_max")]
+    pub map_max_x: f32,
+    #[serde(default = "default_map_min")]
+    pub map_min_y: f32,
+    #[serde(default = "default_map_max")]
+    pub map_max_y: f32,
+    /// Horizontal centers of the left, middle and right lane, normalized to the minimap width.
+    #[serde(
+        default = "default_map_lane_centers",
+        deserialize_with = "map_lane_centers_deserialize"
+    )]
+    pub map_lane_centers: [f32; 3],
+    /// Half the width of a lane, normalized to the minimap width.
+    #[serde(default = "default_map_lane_half_width")]
+    pub map_lane_half_width: f32,
+    /// Distance from the center line of the minimap beyond which a position is in a base,
+    /// normalized to the minimap height.
+    #[serde(default = "default_map_base_distance")]
+    pub map_base_distance: f32,
+    /// Team whose base is at the bottom of the minimap.
+    #[serde(default = "default_map_bottom_team")]
+    pub map_bottom_team: u8,
+    /// JSON encoded list of client API keys. If set, clients have to authenticate with one of them.
+    #[serde(default, deserialize_with = "client_api_keys_deserialize")]
+    pub client_api_keys: Vec<ClientApiKey>,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, AppStateError> {
+        let config: Self = serde_env::from_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the values that deserialize fine but cannot work.
+    pub fn validate(&self) -> Result<(), AppStateError> {
+        // Chat messages are split into words at every character that is not a letter or digit
+        if let Some(word) = self
+            .chat_masked_words
+            .iter()
+            .flatten()
+            .find(|w| w.is_empty() || !w.chars().all(char::is_alphanumeric))
+        {
+            return Err(AppStateError::InvalidConfig(format!(
+                "Invalid chat_masked_words entry {word:?}: only letters and digits can be masked"
+            )));
+        }
+        if [
+            self.map_min_x,
+            self.map_max_x,
+            self.map_min_y,
+            self.map_max_y,
+        ]
+        .iter()
+        .any(|v| !v.is_finite())
+            || self.map_min_x >= self.map_max_x
+            || self.map_min_y >= self.map_max_y
+        {
+            return Err(AppStateError::InvalidConfig(
+                "Invalid map bounds: minimum must be below maximum".to_owned(),
+            ));
+        }
+        let normalized = 0.0..=1.0;
+        if !self.map_lane_centers.iter().all(|c| normalized.contains(c))
+            || !normalized.contains(&self.map_lane_half_width)
+            || !(0.0..=0.5).contains(&self.map_base_distance)
+        {
+            return Err(AppStateError::InvalidConfig(
+                "Invalid map zones: all zones must be within the minimap".to_owned(),
+            ));
+        }
+        if !matches!(self.map_bottom_team, 2 | 3) {
+            return Err(AppStateError::InvalidConfig(
+                "Invalid map_bottom_team: must be 2 or 3".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The minimap of `coords=minimap`, the config has to be validated.
+    pub(crate) fn minimap(&self) -> Minimap {
+        Minimap::new(
+            [self.map_min_x, self.map_min_y],
+            [self.map_max_x, self.map_max_y],
+            MapZones {
+                lane_centers: self.map_lane_centers,
+                lane_half_width: self.map_lane_half_width,
+                base_distance: self.map_base_distance,
+                bottom_team: self.map_bottom_team,
+            },
+        )
+    }
+
+    pub fn listen_address(&self) -> Result<ListenAddress, AppStateError> {
+        if let Some(path) = self.listen_address.strip_prefix("unix:") {
+            return Ok(ListenAddress::Unix(path.into()));
+        }
+        let ip: IpAddr = self
+            .listen_address
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse()
+            .map_err(|e| {
+                AppStateError::InvalidConfig(format!(
+                    "Invalid listen_address {}: {e}",
+                    self.listen_address
+                ))
+            })?;
+        Ok(ListenAddress::Tcp(SocketAddr::new(ip, self.port)))
+    }
+
+    /// The certificate and key paths if TLS is enabled, both have to be set together.
+    pub fn tls_paths(&self) -> Result<Option<(&Path, &Path)>, AppStateError> {
+        match (&self.tls_cert_path, &self.tls_key_path) {
+            (Some(cert), Some(key)) => Ok(Some((cert, key))),
+            (None, None) => Ok(None),
+            _ => Err(AppStateError::InvalidConfig(
+                "tls_cert_path and tls_key_path must be set together".to_owned(),
+            )),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub(crate) struct AppState {
     pub(crate) config: Config,
     pub(crate) http_client: reqwest::Client,
+    pub(crate) metrics: PrometheusHandle,
+    pub(crate) streams: StreamRegistry,
+    pub(crate) clients: ClientKeys,
+    pub(crate) shutdown: CancellationToken,
 }
 
 impl AppState {
-    #[allow(clippy::too_many_lines)]
-    pub(crate) fn from_env() -> Result<AppState, AppStateError> {
-        let config = serde_env::from_env()?;
+    pub(crate) fn new(config: Config, shutdown: CancellationToken) -> Self {
         let http_client = reqwest::Client::new();
-        Ok(Self {
+        let clients = ClientKeys::new(&config.client_api_keys);
+        Self {
             config,
             http_client,
-        })
+            metrics: telemetry::prometheus_handle(),
+            streams: StreamRegistry::default(),
+            clients,
+            shutdown,
+        }
+    }
+
+    /// Rejects new streams once the server has started shutting down.
+    pub(crate) fn ensure_accepting_streams(&self) -> APIResult<()> {
+        if self.shutdown.is_cancelled() {
+            return Err(APIError::StatusMsg {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                message: "Server is shutting down.".to_owned(),
+            });
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,93 @@
+use core::time::Duration;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::auth::ClientKey;
+use crate::error::APIResult;
+use crate::state::AppState;
+
+const UPSTREAM_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[allow(clippy::unused_async)]
+pub(super) async fn health() -> impl IntoResponse {
+    Json(json!({"status": "ok"}))
+}
+
+#[derive(Deserialize)]
+pub(super) struct ReadyQuery {
+    /// Also verify that the Deadlock API is reachable.
+    #[serde(default)]
+    check_upstream: Option<bool>,
+}
+
+fn check_config(state: &AppState) -> Result<(), String> {
+    let config = &state.config;
+    reqwest::Url::parse(&config.deadlock_api_base_url)
+        .map_err(|e| format!("Invalid deadlock_api_base_url: {e}"))?;
+    config.validate().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn check_upstream(state: &AppState) -> Result<(), String> {
+    state
+        .http_client
+        .head(&state.config.deadlock_api_base_url)
+        .timeout(UPSTREAM_CHECK_TIMEOUT)
+        .send()
+        .await
+        .map(drop)
+        .map_err(|e| format!("Deadlock API unreachable: {e}"))
+}
+
+pub(super) async fn ready(
+    Query(query): Query<ReadyQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let mut checks = serde_json::Map::new();
+    let mut ready = true;
+
+    let shutting_down = state.shutdown.is_cancelled();
+    ready &= !shutting_down;
+    checks.insert(
+        "shutdown".to_owned(),
+        json!(if shutting_down { "shutting down" } else { "ok" }),
+    );
+
+    let config = check_config(&state);
+    ready &= config.is_ok();
+    checks.insert(
+        "config".to_owned(),
+        json!(config.err().unwrap_or_else(|| "ok".to_owned())),
+    );
+
+    if query.check_upstream.unwrap_or_default() {
+        let upstream = check_upstream(&state).await;
+        ready &= upstream.is_ok();
+        checks.insert(
+            "upstream".to_owned(),
+            json!(upstream.err().unwrap_or_else(|| "ok".to_owned())),
+        );
+    }
+
+    let (status, message) = if ready {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+    (status, Json(json!({"status": message, "checks": checks})))
+}
+
+/// Lists every watched match, so it requires an API key once keys are configured.
+#[allow(clippy::unused_async)]
+pub(super) async fn streams(
+    State(state): State<AppState>,
+    client_key: ClientKey,
+) -> APIResult<impl IntoResponse> {
+    state.clients.authenticate(&client_key)?;
+    Ok(Json(json!({"streams": state.streams.summary()})))
+}
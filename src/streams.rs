@@ -0,0 +1,139 @@
+use core::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::auth::{Endpoint, StreamPermit};
+use crate::telemetry::ActiveStreamGuard;
+
+/// Parsing progress of a stream, updated by the visitor at the end of every tick.
+#[derive(Debug, Default)]
+pub(crate) struct StreamProgress {
+    tick: AtomicI32,
+    game_time: AtomicU32,
+}
+
+impl StreamProgress {
+    pub(crate) fn update(&self, tick: i32, game_time: f32) {
+        self.tick.store(tick, Ordering::Relaxed);
+        self.game_time.store(game_time.to_bits(), Ordering::Relaxed);
+    }
+
+    fn tick(&self) -> i32 {
+        self.tick.load(Ordering::Relaxed)
+    }
+
+    fn game_time(&self) -> f32 {
+        f32::from_bits(self.game_time.load(Ordering::Relaxed))
+    }
+}
+
+struct RegisteredStream {
+    match_id: u64,
+    endpoint: &'static str,
+    started_at: Instant,
+    progress: Arc<StreamProgress>,
+}
+
+#[derive(Default)]
+struct Registry {
+    next_id: u64,
+    streams: HashMap<u64, RegisteredStream>,
+}
+
+/// All client streams that are currently open.
+#[derive(Clone, Default)]
+pub(crate) struct StreamRegistry {
+    inner: Arc<Mutex<Registry>>,
+}
+
+impl StreamRegistry {
+    pub(crate) fn register(
+        &self,
+        match_id: u64,
+        endpoint: Endpoint,
+        permit: Option<StreamPermit>,
+    ) -> StreamHandle {
+        let endpoint = endpoint.into();
+        let progress = Arc::new(StreamProgress::default());
+        let mut registry = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let id = registry.next_id;
+        registry.next_id += 1;
+        registry.streams.insert(
+            id,
+            RegisteredStream {
+                match_id,
+                endpoint,
+                started_at: Instant::now(),
+                progress: progress.clone(),
+            },
+        );
+        StreamHandle {
+            registry: self.clone(),
+            id,
+            progress,
+            _metrics: ActiveStreamGuard::new(endpoint),
+            _permit: permit,
+        }
+    }
+
+    /// Active streams grouped by match.
+    pub(crate) fn summary(&self) -> Vec<MatchStreams> {
+        let registry = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut matches: BTreeMap<u64, MatchStreams> = BTreeMap::new();
+        for stream in registry.streams.values() {
+            let summary = matches
+                .entry(stream.match_id)
+                .or_insert_with(|| MatchStreams {
+                    match_id: stream.match_id,
+                    ..Default::default()
+                });
+            summary.subscribers += 1;
+            *summary.endpoints.entry(stream.endpoint).or_default() += 1;
+            summary.tick = summary.tick.max(stream.progress.tick());
+            summary.game_time = summary.game_time.max(stream.progress.game_time());
+            summary.uptime_secs = summary
+                .uptime_secs
+                .max(stream.started_at.elapsed().as_secs_f64());
+        }
+        matches.into_values().collect()
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+pub(crate) struct MatchStreams {
+    match_id: u64,
+    subscribers: usize,
+    endpoints: BTreeMap<&'static str, usize>,
+    tick: i32,
+    game_time: f32,
+    uptime_secs: f64,
+}
+
+/// Keeps a stream registered for as long as it is alive.
+pub(crate) struct StreamHandle {
+    registry: StreamRegistry,
+    id: u64,
+    progress: Arc<StreamProgress>,
+    _metrics: ActiveStreamGuard,
+    _permit: Option<StreamPermit>,
+}
+
+impl StreamHandle {
+    pub(crate) fn progress(&self) -> Arc<StreamProgress> {
+        self.progress.clone()
+    }
+}
+
+impl Drop for StreamHandle {
+    fn drop(&mut self) {
+        self.registry
+            .inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .streams
+            .remove(&self.id);
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::OnceLock;
+
+use axum::extract::State;
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use tracing::warn;
+
+use crate::auth::ClientKey;
+use crate::demo_parser::error::DemoParseError;
+use crate::error::{APIResult, UpstreamErrorCode};
+use crate::state::AppState;
+
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Installs the Prometheus recorder on first use and returns a handle to render its metrics.
+pub(crate) fn prometheus_handle() -> PrometheusHandle {
+    PROMETHEUS_HANDLE
+        .get_or_init(|| {
+            let recorder = PrometheusBuilder::new().build_recorder();
+            let handle = recorder.handle();
+            if let Err(e) = metrics::set_global_recorder(recorder) {
+                warn!("Failed to install metrics recorder: {e}");
+            }
+            handle
+        })
+        .clone()
+}
+
+/// Requires an API key once keys are configured, scrapers pass it in the `X-API-Key` header.
+#[allow(clippy::unused_async)]
+pub(super) async fn prometheus_metrics(
+    State(state): State<AppState>,
+    client_key: ClientKey,
+) -> APIResult<String> {
+    state.clients.authenticate(&client_key)?;
+    state.metrics.run_upkeep();
+    Ok(state.metrics.render())
+}
+
+/// Tracks an open client stream in `live_events_active_streams` for as long as it is alive.
+///
+/// Only labelled by endpoint to keep the cardinality bounded, `/v1/streams` lists the matches.
+pub(crate) struct ActiveStreamGuard {
+    endpoint: &'static str,
+}
+
+impl ActiveStreamGuard {
+    pub(crate) fn new(endpoint: &'static str) -> Self {
+        gauge!("live_events_active_streams", "endpoint" => endpoint).increment(1.0);
+        Self { endpoint }
+    }
+}
+
+impl Drop for ActiveStreamGuard {
+    fn drop(&mut self) {
+        gauge!("live_events_active_streams", "endpoint" => self.endpoint).decrement(1.0);
+    }
+}
+
+pub(crate) fn record_sse_event(event_type: &str, bytes: usize) {
+    counter!("live_events_sse_events_total", "event_type" => event_type.to_owned()).increment(1);
+    record_bytes_sent("events", bytes);
+}
+
+pub(crate) fn record_bytes_sent(endpoint: &'static str, bytes: usize) {
+    counter!("live_events_bytes_sent_total", "endpoint" => endpoint).increment(bytes as u64);
+}
+
+pub(crate) fn record_fragment_fetch(seconds: f64, success: bool) {
+    histogram!("live_events_fragment_fetch_duration_seconds").record(seconds);
+    if !success {
+        counter!("live_events_fragment_fetch_errors_total").increment(1);
+    }
+}
+
+pub(crate) fn record_parse_error(error: &DemoParseError) {
+    counter!("live_events_parse_errors_total", "kind" => error.kind()).increment(1);
+}
+
+pub(crate) fn record_upstream_retry(request: &'static str) {
+    counter!("live_events_upstream_retries_total", "request" => request).increment(1);
+}
+
+pub(crate) fn record_upstream_error(code: UpstreamErrorCode) {
+    counter!("live_events_upstream_errors_total", "code" => <&'static str>::from(code))
+        .increment(1);
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn record_channel_backlog(len: usize) {
+    histogram!("live_events_channel_backlog").record(len as f64);
+}
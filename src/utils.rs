@@ -1,8 +1,14 @@
 use core::num::TryFromIntError;
 use core::str::FromStr;
+use core::time::Duration;
 
 use reqwest::Response;
 use serde::{Deserialize, Deserializer};
+use tracing::{info, warn};
+use tryhard::RetryPolicy;
+
+use crate::error::{APIError, APIResult, UpstreamErrorCode};
+use crate::telemetry;
 
 // Query Parameter Parsing
 #[derive(Debug, Deserialize)]
@@ -84,13 +90,13 @@ pub(crate) struct SpectateMatchResponse {
 
 pub(crate) async fn spectate_match(
     http_client: &reqwest::Client,
+    api_base_url: &str,
     match_id: u64,
     api_key: Option<&str>,
 ) -> reqwest::Result<SpectateMatchResponse> {
+    let api_base_url = api_base_url.strip_suffix('/').unwrap_or(api_base_url);
     http_client
-        .get(format!(
-            "https://api.deadlock-api.com/v1/matches/{match_id}/live/url"
-        ))
+        .get(format!("{api_base_url}/v1/matches/{match_id}/live/url"))
         .header("X-API-Key", api_key.unwrap_or_default())
         .send()
         .await?
@@ -111,3 +117,46 @@ pub(crate) async fn live_demo_exists(
         .and_then(Response::error_for_status)
         .map(drop)
 }
+
+/// Resolves the broadcast url of a live match and waits for its demo to become available.
+pub(crate) async fn spectate_live_demo(
+    http_client: &reqwest::Client,
+    api_base_url: &str,
+    match_id: u64,
+    api_key: Option<&str>,
+) -> APIResult<String> {
+    info!("Spectating match {match_id}");
+    // Only retry failures that may go away, a match that is not live won't become live in a second
+    let response =
+        tryhard::retry_fn(|| spectate_match(http_client, api_base_url, match_id, api_key))
+            .retries(3)
+            .custom_backoff(|_, e: &reqwest::Error| {
+                if UpstreamErrorCode::from_request_error(e).is_transient() {
+                    RetryPolicy::Delay(Duration::from_millis(200))
+                } else {
+                    RetryPolicy::Break
+                }
+            })
+            .on_retry(|_, _, _| {
+                telemetry::record_upstream_retry("spectate_match");
+                core::future::ready(())
+            })
+            .await?;
+
+    // Wait for the demo to be available
+    tryhard::retry_fn(|| live_demo_exists(http_client, &response.broadcast_url))
+        .retries(60)
+        .fixed_backoff(Duration::from_millis(500))
+        .on_retry(|_, _, _| {
+            telemetry::record_upstream_retry("live_demo_exists");
+            core::future::ready(())
+        })
+        .await
+        .map_err(|e| {
+            warn!("Demo of match {match_id} did not become available: {e}");
+            APIError::upstream(UpstreamErrorCode::DemoNotAvailable)
+        })?;
+
+    info!("Demo available for match {match_id}");
+    Ok(response.broadcast_url)
+}
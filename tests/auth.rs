@@ -0,0 +1,93 @@
+mod common;
+
+use common::spawn_app_with;
+use reqwest::StatusCode;
+use serde_json::json;
+
+fn client_api_keys() -> serde_json::Value {
+    let keys = json!([
+        {"key": "limited", "max_streams_per_minute": 1},
+        {"key": "demo-only", "allowed_endpoints": ["demo"]},
+    ]);
+    json!({"client_api_keys": keys.to_string()})
+}
+
+#[tokio::test]
+async fn missing_or_invalid_key_is_unauthorized() {
+    let app = spawn_app_with(client_api_keys()).await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{app}/v1/matches/1/live/demo/events"))
+        .send()
+        .await
+        .expect("Request failed");
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .get(format!("{app}/v1/matches/1/live/demo/events"))
+        .header("X-API-Key", "invalid")
+        .send()
+        .await
+        .expect("Request failed");
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn endpoints_are_restricted() {
+    let app = spawn_app_with(client_api_keys()).await;
+
+    let response = reqwest::get(format!(
+        "{app}/v1/matches/1/live/demo/events?api_key=demo-only"
+    ))
+    .await
+    .expect("Request failed");
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn new_streams_are_rate_limited() {
+    let app = spawn_app_with(client_api_keys()).await;
+    let url = format!("{app}/v1/matches/1/live/demo/events?api_key=limited");
+
+    let response = reqwest::get(&url).await.expect("Request failed");
+    assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let response = reqwest::get(&url).await.expect("Request failed");
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn status_endpoints_are_open() {
+    let app = spawn_app_with(client_api_keys()).await;
+
+    let response = reqwest::get(format!("{app}/health"))
+        .await
+        .expect("Request failed");
+
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn streams_and_metrics_require_a_key() {
+    let app = spawn_app_with(client_api_keys()).await;
+    let client = reqwest::Client::new();
+
+    for path in ["v1/streams", "metrics"] {
+        let response = client
+            .get(format!("{app}/{path}"))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{path}");
+
+        let response = client
+            .get(format!("{app}/{path}"))
+            .header("X-API-Key", "demo-only")
+            .send()
+            .await
+            .expect("Request failed");
+        assert!(response.status().is_success(), "{path}");
+    }
+}
@@ -0,0 +1,185 @@
+//! Shared harness for the integration tests.
+//!
+//! The mock upstream stands in for both the Deadlock API (`/v1/matches/{match_id}/live/url`)
+//! and the broadcast server the returned url points at. Broadcasts are served from recorded
+//! fragments in `tests/fixtures/broadcasts/{match_id}/`, see the README in that directory.
+#![allow(dead_code)]
+
+use core::time::Duration;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+use axum::ServiceExt;
+use axum::extract::{Path as UrlPath, Request, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use deadlock_live_events::{Config, router};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+pub fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/broadcasts")
+}
+
+/// Match ids of all recorded broadcasts in the fixtures directory, panics if there are none.
+pub fn fixture_match_ids() -> Vec<u64> {
+    let entries = std::fs::read_dir(fixtures_dir()).expect("Failed to read fixtures directory");
+    let mut match_ids: Vec<u64> = entries
+        .filter_map(Result::ok)
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().to_str()?.parse().ok())
+        .collect();
+    match_ids.sort_unstable();
+    assert!(
+        !match_ids.is_empty(),
+        "No recorded broadcasts in {}, see the README there to record one",
+        fixtures_dir().display()
+    );
+    match_ids
+}
+
+async fn bind_local() -> (TcpListener, String) {
+    let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+        .await
+        .expect("Failed to bind test listener");
+    let address = listener.local_addr().expect("Failed to get local address");
+    (listener, format!("http://{address}"))
+}
+
+#[derive(Clone)]
+struct MockUpstream {
+    base_url: String,
+}
+
+async fn live_url(
+    UrlPath(match_id): UrlPath<u64>,
+    State(upstream): State<MockUpstream>,
+) -> Response {
+    if !fixtures_dir().join(match_id.to_string()).is_dir() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    Json(json!({"broadcast_url": format!("{}/tv/{match_id}", upstream.base_url)})).into_response()
+}
+
+fn fixture_file(path: &Path) -> Response {
+    match std::fs::read(path) {
+        Ok(bytes) => bytes.into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn sync(UrlPath(match_id): UrlPath<u64>) -> Response {
+    fixture_file(&fixtures_dir().join(match_id.to_string()).join("sync.json"))
+}
+
+async fn fragment(UrlPath((match_id, fragment, kind)): UrlPath<(u64, u64, String)>) -> Response {
+    fixture_file(
+        &fixtures_dir()
+            .join(match_id.to_string())
+            .join(format!("{fragment}_{kind}")),
+    )
+}
+
+/// Starts the mock upstream and returns its base url.
+pub async fn spawn_mock_upstream() -> String {
+    let (listener, base_url) = bind_local().await;
+    let router = Router::new()
+        .route("/v1/matches/{match_id}/live/url", get(live_url))
+        .route("/tv/{match_id}/sync", get(sync))
+        .route("/tv/{match_id}/{fragment}/{kind}", get(fragment))
+        .with_state(MockUpstream {
+            base_url: base_url.clone(),
+        });
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    base_url
+}
+
+/// Builds a config pointing at the given mock upstream, with `overrides` merged on top.
+pub fn test_config(upstream_url: &str, overrides: Value) -> Config {
+    let mut config = json!({"deadlock_api_base_url": upstream_url});
+    if let (Some(config), Value::Object(overrides)) = (config.as_object_mut(), overrides) {
+        config.extend(overrides);
+    }
+    serde_json::from_value(config).expect("Invalid test config")
+}
+
+/// Starts the application router against a fresh mock upstream and returns its base url.
+pub async fn spawn_app() -> String {
+    spawn_app_with(json!({})).await
+}
+
+pub async fn spawn_app_with(overrides: Value) -> String {
+    spawn_app_with_shutdown(overrides, CancellationToken::new()).await
+}
+
+pub async fn spawn_app_with_shutdown(overrides: Value, shutdown: CancellationToken) -> String {
+    let upstream_url = spawn_mock_upstream().await;
+    let router = router(test_config(&upstream_url, overrides), shutdown);
+    let (listener, base_url) = bind_local().await;
+    tokio::spawn(async move {
+        axum::serve(listener, ServiceExt::<Request>::into_make_service(router)).await
+    });
+    base_url
+}
+
+#[derive(Debug, Clone)]
+pub struct SseEvent {
+    pub event: String,
+    pub data: String,
+}
+
+impl SseEvent {
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.data).unwrap_or(Value::String(self.data.clone()))
+    }
+}
+
+fn parse_sse_event(block: &str) -> Option<SseEvent> {
+    let mut event = None;
+    let mut data = vec![];
+    for line in block.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            event = Some(value.trim_start().to_owned());
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    if event.is_none() && data.is_empty() {
+        // Keep-alive comment
+        return None;
+    }
+    Some(SseEvent {
+        event: event.unwrap_or_else(|| "message".to_owned()),
+        data: data.join("\n"),
+    })
+}
+
+/// Reads SSE events from `response` until `stop` returns true, the stream ends or `timeout`
+/// elapses.
+pub async fn collect_sse_events(
+    mut response: reqwest::Response,
+    timeout: Duration,
+    mut stop: impl FnMut(&SseEvent) -> bool,
+) -> Vec<SseEvent> {
+    let mut events = vec![];
+    let mut buffer = String::new();
+    let deadline = tokio::time::Instant::now() + timeout;
+    while let Ok(Ok(Some(chunk))) = tokio::time::timeout_at(deadline, response.chunk()).await {
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(end) = buffer.find("\n\n") {
+            let block: String = buffer.drain(..end + 2).collect();
+            let Some(event) = parse_sse_event(&block) else {
+                continue;
+            };
+            let done = stop(&event);
+            events.push(event);
+            if done {
+                return events;
+            }
+        }
+    }
+    events
+}
@@ -0,0 +1,147 @@
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+use deadlock_live_events::{Config, ListenAddress};
+use serde_json::{Value, json};
+
+fn config(values: Value) -> Config {
+    serde_json::from_value(values).expect("Invalid test config")
+}
+
+#[test]
+fn listen_address_defaults_to_all_ipv4_interfaces() {
+    assert_eq!(
+        config(json!({})).listen_address().expect("Invalid address"),
+        ListenAddress::Tcp(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 3000))
+    );
+}
+
+#[test]
+fn listen_address_accepts_ipv6() {
+    for address in ["::1", "[::1]"] {
+        assert_eq!(
+            config(json!({"listen_address": address, "port": 8080}))
+                .listen_address()
+                .expect("Invalid address"),
+            ListenAddress::Tcp(SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 8080)),
+            "{address}"
+        );
+    }
+}
+
+#[test]
+fn listen_address_accepts_unix_sockets() {
+    assert_eq!(
+        config(json!({"listen_address": "unix:/run/live-events.sock"}))
+            .listen_address()
+            .expect("Invalid address"),
+        ListenAddress::Unix(PathBuf::from("/run/live-events.sock"))
+    );
+}
+
+#[test]
+fn listen_address_rejects_hostnames() {
+    assert!(
+        config(json!({"listen_address": "localhost"}))
+            .listen_address()
+            .is_err()
+    );
+}
+
+#[test]
+fn tls_requires_cert_and_key() {
+    assert!(
+        config(json!({}))
+            .tls_paths()
+            .expect("Invalid TLS")
+            .is_none()
+    );
+    assert_eq!(
+        config(json!({"tls_cert_path": "cert.pem", "tls_key_path": "key.pem"}))
+            .tls_paths()
+            .expect("Invalid TLS"),
+        Some((Path::new("cert.pem"), Path::new("key.pem")))
+    );
+    assert!(
+        config(json!({"tls_cert_path": "cert.pem"}))
+            .tls_paths()
+            .is_err()
+    );
+    assert!(
+        config(json!({"tls_key_path": "key.pem"}))
+            .tls_paths()
+            .is_err()
+    );
+}
+
+#[test]
+fn cors_allowed_origins_keep_ipv6_brackets() {
+    let config = config(json!({
+        "cors_allowed_origins": "http://[::1]:3000, https://example.com,"
+    }));
+    assert_eq!(
+        config.cors_allowed_origins,
+        Some(vec![
+            "http://[::1]:3000".to_owned(),
+            "https://example.com".to_owned()
+        ])
+    );
+}
+
+#[test]
+fn chat_masked_words_must_be_letters_or_digits() {
+    assert!(
+        config(json!({"chat_masked_words": "bad,Ärger,1337"}))
+            .validate()
+            .is_ok()
+    );
+    assert!(
+        config(json!({"chat_masked_words": "bad,f*ck"}))
+            .validate()
+            .is_err()
+    );
+    assert!(
+        config(json!({"chat_masked_words": "two words"}))
+            .validate()
+            .is_err()
+    );
+}
+
+#[test]
+fn map_bounds_must_be_ordered_and_finite() {
+    assert!(config(json!({})).validate().is_ok());
+    assert!(
+        config(json!({"map_min_x": 0.0, "map_max_x": 0.0}))
+            .validate()
+            .is_err()
+    );
+    assert!(
+        config(json!({"map_min_y": 100.0, "map_max_y": -100.0}))
+            .validate()
+            .is_err()
+    );
+    let mut infinite = config(json!({}));
+    infinite.map_max_x = f32::INFINITY;
+    assert!(infinite.validate().is_err());
+}
+
+#[test]
+fn map_zones_must_be_within_the_minimap() {
+    assert!(
+        config(json!({"map_lane_centers": "0.1,0.4,0.9", "map_bottom_team": 3}))
+            .validate()
+            .is_ok()
+    );
+    assert!(
+        config(json!({"map_lane_centers": "0.1,0.4,1.5"}))
+            .validate()
+            .is_err()
+    );
+    assert!(
+        config(json!({"map_base_distance": 0.6}))
+            .validate()
+            .is_err()
+    );
+    assert!(config(json!({"map_bottom_team": 1})).validate().is_err());
+    assert!(serde_json::from_value::<Config>(json!({"map_lane_centers": "0.2,0.8"})).is_err());
+}
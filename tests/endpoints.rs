@@ -0,0 +1,140 @@
+mod common;
+
+use common::{fixture_match_ids, spawn_app, spawn_app_with, spawn_app_with_shutdown};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+
+#[tokio::test]
+async fn metrics_are_rendered() {
+    let app = spawn_app().await;
+    // Produce at least one metric
+    reqwest::get(format!("{app}/v1/matches/1/live/demo/events"))
+        .await
+        .expect("Request failed");
+
+    let response = reqwest::get(format!("{app}/metrics"))
+        .await
+        .expect("Request failed");
+
+    assert!(response.status().is_success());
+    let body = response.text().await.expect("Failed to read body");
+    assert!(body.contains("live_events_upstream_errors_total"));
+}
+
+#[tokio::test]
+async fn health_is_ok() {
+    let app = spawn_app().await;
+
+    let response = reqwest::get(format!("{app}/health"))
+        .await
+        .expect("Request failed");
+
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn ready_checks_upstream() {
+    let app = spawn_app().await;
+
+    let response = reqwest::get(format!("{app}/ready?check_upstream=true"))
+        .await
+        .expect("Request failed");
+
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.expect("Invalid JSON");
+    assert_eq!(body["checks"]["upstream"], "ok");
+}
+
+#[tokio::test]
+async fn ready_fails_on_invalid_config() {
+    let app = spawn_app_with(json!({"deadlock_api_base_url": "not a url"})).await;
+
+    let response = reqwest::get(format!("{app}/ready"))
+        .await
+        .expect("Request failed");
+
+    assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn ready_fails_on_invalid_map_bounds() {
+    let app = spawn_app_with(json!({"map_min_x": 100.0, "map_max_x": -100.0})).await;
+
+    let response = reqwest::get(format!("{app}/ready"))
+        .await
+        .expect("Request failed");
+
+    assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = response.json().await.expect("Invalid JSON");
+    assert_ne!(body["checks"]["config"], "ok");
+}
+
+#[tokio::test]
+async fn no_streams_are_active_initially() {
+    let app = spawn_app().await;
+
+    let response = reqwest::get(format!("{app}/v1/streams"))
+        .await
+        .expect("Request failed");
+
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.expect("Invalid JSON");
+    assert_eq!(body["streams"], json!([]));
+}
+
+#[tokio::test]
+async fn shutdown_rejects_new_streams() {
+    let shutdown = CancellationToken::new();
+    let app = spawn_app_with_shutdown(json!({}), shutdown.clone()).await;
+    shutdown.cancel();
+
+    let response = reqwest::get(format!("{app}/v1/matches/1/live/demo/events"))
+        .await
+        .expect("Request failed");
+    assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+    let response = reqwest::get(format!("{app}/ready"))
+        .await
+        .expect("Request failed");
+    assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn schema_of_unknown_match_is_not_found() {
+    let app = spawn_app().await;
+
+    let response = reqwest::get(format!("{app}/debug/matches/1/schema"))
+        .await
+        .expect("Request failed");
+
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn schema_of_recorded_broadcasts_has_no_missing_fields() {
+    for match_id in fixture_match_ids() {
+        let app = spawn_app().await;
+        let response = reqwest::get(format!(
+            "{app}/debug/matches/{match_id}/schema?duration_secs=30"
+        ))
+        .await
+        .expect("Request failed");
+        assert!(response.status().is_success(), "match {match_id}");
+
+        let body: serde_json::Value = response.json().await.expect("Invalid JSON");
+        let entity_types = body["entity_types"]
+            .as_object()
+            .expect("entity_types is not an object");
+        assert!(
+            !entity_types.is_empty(),
+            "match {match_id}: no entity types"
+        );
+        for (entity_type, schema) in entity_types {
+            assert_eq!(
+                schema["missing_fields"],
+                json!([]),
+                "match {match_id}: {entity_type} has missing fields"
+            );
+        }
+    }
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use core::time::Duration;
+
+use common::{SseEvent, collect_sse_events, fixture_match_ids, spawn_app};
+
+const STREAM_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[tokio::test]
+async fn unknown_match_is_an_error() {
+    let app = spawn_app().await;
+
+    let response = reqwest::get(format!("{app}/v1/matches/1/live/demo/events"))
+        .await
+        .expect("Request failed");
+
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    let body: serde_json::Value = response.json().await.expect("Invalid JSON");
+    assert_eq!(body["code"], "match_not_found");
+}
+
+#[tokio::test]
+async fn unsupported_user_message_is_rejected() {
+    let app = spawn_app().await;
+
+    let response = reqwest::get(format!(
+        "{app}/v1/matches/1/live/demo/events?subscribed_user_messages=HeroKilled,NotAMessage"
+    ))
+    .await
+    .expect("Request failed");
+
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn team_communication_is_not_a_user_message() {
+    let app = spawn_app().await;
+
+    for message in ["ChatMsg", "TeamMsg", "ChatWheel", "MapPing", "AbilityPing"] {
+        let response = reqwest::get(format!(
+            "{app}/v1/matches/1/live/demo/events?subscribed_user_messages={message}"
+        ))
+        .await
+        .expect("Request failed");
+
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::BAD_REQUEST,
+            "{message}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn invalid_region_is_rejected() {
+    let app = spawn_app().await;
+
+    for region in ["1,2", "nan,0,1,1"] {
+        let response = reqwest::get(format!(
+            "{app}/v1/matches/1/live/demo/events?region={region}"
+        ))
+        .await
+        .expect("Request failed");
+
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::BAD_REQUEST,
+            "{region}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn streams_recorded_broadcasts() {
+    for match_id in fixture_match_ids() {
+        let app = spawn_app().await;
+        let response = reqwest::get(format!("{app}/v1/matches/{match_id}/live/demo/events"))
+            .await
+            .expect("Request failed");
+        assert!(response.status().is_success(), "match {match_id}");
+
+        let events = collect_sse_events(response, STREAM_TIMEOUT, |e| e.event == "end").await;
+        assert_recorded_broadcast(match_id, &events);
+    }
+}
+
+fn assert_recorded_broadcast(match_id: u64, events: &[SseEvent]) {
+    let first = events.first().expect("No events received");
+    assert_eq!(first.event, "message", "match {match_id}");
+    assert_eq!(first.json()["status"], "connected", "match {match_id}");
+
+    let last = events.last().expect("No events received");
+    assert_eq!(last.event, "end", "match {match_id}");
+    assert_eq!(last.json()["reason"], "match_over", "match {match_id}");
+
+    // The stream starts with the connection event, followed by the entities of the first
+    // tick, the end of that tick and finally the end of the stream.
+    let first_entity = events
+        .iter()
+        .position(|e| e.event.ends_with("_entity_created"))
+        .unwrap_or_else(|| panic!("match {match_id}: no entity events"));
+    let first_tick_end = events
+        .iter()
+        .position(|e| e.event == "tick_end")
+        .unwrap_or_else(|| panic!("match {match_id}: no tick_end events"));
+    assert!(
+        0 < first_entity && first_entity < first_tick_end,
+        "match {match_id}: expected start, entity events, tick_end, end but got {:?}",
+        events.iter().take(5).map(|e| &e.event).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        events.iter().position(|e| e.event == "end"),
+        Some(events.len() - 1),
+        "match {match_id}: events after end"
+    );
+    assert!(
+        events[first_entity].json()["tick"].is_number(),
+        "match {match_id}: entity event without tick"
+    );
+
+    let match_ended = events
+        .iter()
+        .find(|e| e.event == "match_ended")
+        .unwrap_or_else(|| panic!("match {match_id}: no match_ended event"))
+        .json();
+    assert!(
+        matches!(match_ended["winning_team"].as_u64(), Some(2 | 3)),
+        "match {match_id}: winning_team is {}",
+        match_ended["winning_team"]
+    );
+
+    assert!(
+        !events
+            .iter()
+            .any(|e| e.event.starts_with("ability_entity_")),
+        "match {match_id}: ability updates are sent without subscribing to them"
+    );
+
+    let controllers: Vec<_> = events
+        .iter()
+        .filter(|e| e.event == "player_controller_entity_created")
+        .map(SseEvent::json)
+        .collect();
+    assert!(
+        !controllers.is_empty(),
+        "match {match_id}: no player controllers"
+    );
+    for field in ["steam_id", "hero_id", "team", "net_worth"] {
+        assert!(
+            controllers.iter().any(|c| !c[field].is_null()),
+            "match {match_id}: player_controller.{field} is always null"
+        );
+    }
+
+    let pawns: Vec<_> = events
+        .iter()
+        .filter(|e| e.event.starts_with("player_pawn_entity_"))
+        .map(SseEvent::json)
+        .collect();
+    for field in ["health", "position"] {
+        assert!(
+            pawns.iter().any(|p| !p[field].is_null()),
+            "match {match_id}: player_pawn.{field} is always null"
+        );
+    }
+}
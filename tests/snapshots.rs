@@ -0,0 +1,164 @@
+//! Golden snapshot tests for the entity extractors.
+//!
+//! For every recorded broadcast and every entity type seen in it, the first `*_entity_created`
+//! event and the list of fields that were `null` in every event of that type are compared
+//! against `tests/snapshots/{match_id}/{entity_type}.json`. A field hash that stops matching
+//! after a game patch shows up as a new entry in `always_null`.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to (re)write the snapshots.
+
+mod common;
+
+use core::time::Duration;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use common::{SseEvent, collect_sse_events, fixture_match_ids, spawn_app};
+use serde_json::{Value, json};
+
+const STREAM_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn snapshots_dir(match_id: u64) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(match_id.to_string())
+}
+
+fn update_snapshots() -> bool {
+    std::env::var("UPDATE_SNAPSHOTS").is_ok_and(|v| v == "1")
+}
+
+#[derive(Default)]
+struct EntityTypeSnapshot {
+    first_created: Option<Value>,
+    fields: BTreeSet<String>,
+    non_null_fields: BTreeSet<String>,
+}
+
+impl EntityTypeSnapshot {
+    fn add(&mut self, event: &SseEvent) {
+        let data = event.json();
+        let Some(object) = data.as_object() else {
+            return;
+        };
+        for (field, value) in object {
+            self.fields.insert(field.clone());
+            if !value.is_null() {
+                self.non_null_fields.insert(field.clone());
+            }
+        }
+        if event.event.ends_with("_entity_created") && self.first_created.is_none() {
+            self.first_created = Some(data);
+        }
+    }
+
+    fn always_null(&self) -> Vec<&String> {
+        self.fields.difference(&self.non_null_fields).collect()
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "first_created": self.first_created,
+            "always_null": self.always_null(),
+        })
+    }
+}
+
+fn group_by_entity_type(events: &[SseEvent]) -> BTreeMap<String, EntityTypeSnapshot> {
+    let mut snapshots: BTreeMap<String, EntityTypeSnapshot> = BTreeMap::new();
+    for event in events {
+        let Some(entity_type) = ["_entity_created", "_entity_updated", "_entity_deleted"]
+            .iter()
+            .find_map(|suffix| event.event.strip_suffix(suffix))
+        else {
+            continue;
+        };
+        snapshots
+            .entry(entity_type.to_owned())
+            .or_default()
+            .add(event);
+    }
+    snapshots
+}
+
+fn check_snapshots(match_id: u64, snapshots: &BTreeMap<String, EntityTypeSnapshot>) {
+    let dir = snapshots_dir(match_id);
+    if update_snapshots() {
+        std::fs::create_dir_all(&dir).expect("Failed to create snapshot directory");
+    } else {
+        assert!(
+            dir.is_dir(),
+            "match {match_id}: missing snapshots in {}, run with UPDATE_SNAPSHOTS=1 to write them",
+            dir.display()
+        );
+    }
+    assert!(
+        !snapshots.is_empty(),
+        "match {match_id}: no entity events streamed"
+    );
+
+    let mut failures = vec![];
+    for (entity_type, snapshot) in snapshots {
+        let always_null = snapshot.always_null();
+        if !always_null.is_empty() {
+            eprintln!("match {match_id}: {entity_type} fields always null: {always_null:?}");
+        }
+
+        let path = dir.join(format!("{entity_type}.json"));
+        let actual = serde_json::to_string_pretty(&snapshot.to_json())
+            .expect("Failed to serialize snapshot")
+            + "\n";
+        if update_snapshots() {
+            std::fs::write(&path, actual).expect("Failed to write snapshot");
+            continue;
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{entity_type}: snapshot mismatch\n--- expected\n{expected}+++ actual\n{actual}"
+            )),
+            Err(_) => failures.push(format!(
+                "{entity_type}: missing snapshot {}",
+                path.display()
+            )),
+        }
+    }
+
+    // Entity types that have a snapshot but were never streamed
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        for entry in entries.filter_map(Result::ok) {
+            let file_name = entry.file_name();
+            let Some(entity_type) = file_name.to_str().and_then(|f| f.strip_suffix(".json")) else {
+                continue;
+            };
+            if !snapshots.contains_key(entity_type) {
+                if update_snapshots() {
+                    std::fs::remove_file(entry.path()).expect("Failed to remove snapshot");
+                } else {
+                    failures.push(format!("{entity_type}: no events streamed"));
+                }
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "match {match_id}: {} snapshot failures (run with UPDATE_SNAPSHOTS=1 to update)\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[tokio::test]
+async fn entity_extractors_match_snapshots() {
+    for match_id in fixture_match_ids() {
+        let app = spawn_app().await;
+        let response = reqwest::get(format!("{app}/v1/matches/{match_id}/live/demo/events"))
+            .await
+            .expect("Request failed");
+        assert!(response.status().is_success(), "match {match_id}");
+
+        let events = collect_sse_events(response, STREAM_TIMEOUT, |e| e.event == "end").await;
+        check_snapshots(match_id, &group_by_entity_type(&events));
+    }
+}